@@ -0,0 +1,82 @@
+#![feature(test)]
+
+#[cfg(all(test, feature = "compact_presence"))]
+mod benches {
+    extern crate test;
+
+    use serenity::json::prelude::{from_str, from_value, json, to_string};
+    use serenity::model::gateway::Presence;
+
+    use self::test::Bencher;
+
+    fn presence() -> Presence {
+        from_value(json!({
+            "user": {"id": "80351110224678912"},
+            "status": "dnd",
+            "guild_id": "81384788765712384",
+            "client_status": {"desktop": "dnd", "mobile": "idle"},
+            "activities": [
+                {
+                    "type": 4,
+                    "name": "Custom Status",
+                    "state": "Working on something",
+                    "emoji": {"name": "\u{1F6E0}"},
+                    "created_at": 1_650_000_000_000_u64,
+                },
+                {
+                    "type": 2,
+                    "name": "Spotify",
+                    "id": "spotify:1",
+                    "flags": 48,
+                    "details": "Song title",
+                    "state": "Artist; Other artist",
+                    "sync_id": "6rqhFgbbKwnb9MLmUQDhG6",
+                    "session_id": "e8d5b3a41f4f5e3ad6bca2a3bc6ca2b7",
+                    "party": {"id": "spotify:80351110224678912"},
+                    "assets": {
+                        "large_image": "spotify:ab67616d0000b273a8f4a0fbd5c4b0a6d1c1e5e2",
+                        "large_text": "Album title",
+                    },
+                    "timestamps": {"start": 1_650_000_000_000_u64, "end": 1_650_000_200_000_u64},
+                    "created_at": 1_650_000_000_000_u64,
+                },
+                {
+                    "type": 0,
+                    "name": "Game",
+                    "application_id": "383226320970055681",
+                    "details": "In a match",
+                    "state": "Ranked",
+                    "timestamps": {"start": 1_650_000_000_000_u64},
+                    "assets": {"large_image": "565945769320775680", "large_text": "Map"},
+                    "buttons": ["Join", "Watch"],
+                    "created_at": 1_650_000_000_000_u64,
+                },
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[bench]
+    fn serialize_json(b: &mut Bencher) {
+        let presence = presence();
+        b.iter(|| to_string(&presence).unwrap())
+    }
+
+    #[bench]
+    fn serialize_compact(b: &mut Bencher) {
+        let presence = presence();
+        b.iter(|| presence.to_compact_bytes().unwrap())
+    }
+
+    #[bench]
+    fn deserialize_json(b: &mut Bencher) {
+        let string = to_string(&presence()).unwrap();
+        b.iter(|| from_str::<Presence>(&string).unwrap())
+    }
+
+    #[bench]
+    fn deserialize_compact(b: &mut Bencher) {
+        let bytes = presence().to_compact_bytes().unwrap();
+        b.iter(|| Presence::from_compact_bytes(&bytes).unwrap())
+    }
+}
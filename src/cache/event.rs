@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::time::Instant;
 
 use super::{Cache, CacheUpdate};
 use crate::model::channel::{Channel, GuildChannel, Message};
@@ -31,6 +32,7 @@ use crate::model::event::{
     UserUpdateEvent,
     VoiceStateUpdateEvent,
 };
+use crate::model::gateway::{Presence, PresenceUser};
 use crate::model::guild::{Guild, Member, Role};
 use crate::model::user::{CurrentUser, OnlineStatus};
 use crate::model::voice::VoiceState;
@@ -233,6 +235,10 @@ impl CacheUpdate for GuildDeleteEvent {
                     }
                 }
 
+                for user_id in guild.1.members.keys() {
+                    cache.prune_status_change(*user_id);
+                }
+
                 Some(guild.1)
             },
             None => None,
@@ -275,12 +281,14 @@ impl CacheUpdate for GuildMemberRemoveEvent {
     type Output = Member;
 
     fn update(&mut self, cache: &Cache) -> Option<Self::Output> {
-        if let Some(mut guild) = cache.guilds.get_mut(&self.guild_id) {
+        let member = cache.guilds.get_mut(&self.guild_id).and_then(|mut guild| {
             guild.member_count -= 1;
-            return guild.members.remove(&self.user.id);
-        }
+            guild.members.remove(&self.user.id)
+        });
 
-        None
+        cache.prune_status_change(self.user.id);
+
+        member
     }
 }
 
@@ -517,52 +525,78 @@ impl CacheUpdate for MessageUpdateEvent {
 }
 
 impl CacheUpdate for PresenceUpdateEvent {
-    type Output = ();
+    type Output = Presence;
 
-    fn update(&mut self, cache: &Cache) -> Option<()> {
+    fn update(&mut self, cache: &Cache) -> Option<Presence> {
         if let Some(user) = self.presence.user.to_user() {
             cache.update_user_entry(&user);
         }
 
         if let Some(user) = cache.user(self.presence.user.id) {
-            self.presence.user.update_with_user(user);
+            let mut cached = PresenceUser::from(user);
+            cached.patch_from(&self.presence.user);
+            self.presence.user.overwrite_from(cached);
         }
 
-        if let Some(guild_id) = self.presence.guild_id {
-            if let Some(mut guild) = cache.guilds.get_mut(&guild_id) {
-                // If the member went offline, remove them from the presence list.
-                if self.presence.status == OnlineStatus::Offline {
-                    guild.presences.remove(&self.presence.user.id);
-                } else {
-                    guild.presences.insert(self.presence.user.id, self.presence.clone());
-                }
+        // Compared with the status last received for the user, from any
+        // guild, so that users sharing several guilds with the current user
+        // do not appear to change status when their presence arrives from
+        // each of them.
+        let user_id = self.presence.user.id;
+        let status = self.presence.status;
+        let changed = cache
+            .status_changes
+            .get(&user_id)
+            .map_or(status != OnlineStatus::Offline, |change| change.0 != status);
+        if changed {
+            cache.status_changes.insert(user_id, (status, Instant::now()));
+        }
 
-                // Create a partial member instance out of the presence update
-                // data.
-                if let Some(user) = self.presence.user.to_user() {
-                    guild.members.entry(self.presence.user.id).or_insert_with(|| Member {
-                        deaf: false,
-                        guild_id,
-                        joined_at: None,
-                        mute: false,
-                        nick: None,
-                        user,
-                        roles: vec![],
-                        pending: false,
-                        premium_since: None,
-                        permissions: None,
-                        avatar: None,
-                        communication_disabled_until: None,
-                    });
-                }
+        let old = if let Some(guild_id) = self.presence.guild_id {
+            cache.presence_guilds.insert(self.presence.user.id, guild_id);
+
+            let mut guild = cache.guilds.get_mut(&guild_id)?;
+
+            // If the member went offline, remove them from the presence list.
+            let old = if self.presence.status == OnlineStatus::Offline {
+                guild.presences.remove(&self.presence.user.id)
+            } else {
+                guild.presences.insert(self.presence.user.id, self.presence.clone())
+            };
+
+            // Create a partial member instance out of the presence update
+            // data.
+            if let Some(user) = self.presence.user.to_user() {
+                guild.members.entry(self.presence.user.id).or_insert_with(|| Member {
+                    deaf: false,
+                    guild_id,
+                    joined_at: None,
+                    mute: false,
+                    nick: None,
+                    user,
+                    roles: vec![],
+                    pending: false,
+                    premium_since: None,
+                    permissions: None,
+                    avatar: None,
+                    communication_disabled_until: None,
+                });
             }
-        } else if self.presence.status == OnlineStatus::Offline {
-            cache.presences.remove(&self.presence.user.id);
+
+            old
         } else {
-            cache.presences.insert(self.presence.user.id, self.presence.clone());
-        }
+            let old = cache.presence(self.presence.user.id);
 
-        None
+            if self.presence.status == OnlineStatus::Offline {
+                cache.remove_presence(self.presence.user.id);
+            } else {
+                cache.insert_presence(&self.presence);
+            }
+
+            old
+        };
+
+        old
     }
 }
 
@@ -571,7 +605,7 @@ impl CacheUpdate for PresencesReplaceEvent {
 
     fn update(&mut self, cache: &Cache) -> Option<()> {
         for presence in &self.presences {
-            cache.presences.insert(presence.user.id, presence.clone());
+            cache.insert_presence(presence);
         }
 
         None
@@ -612,15 +646,22 @@ impl CacheUpdate for ReadyEvent {
         // `ready.private_channels` will always be empty, and possibly be removed in the future.
         // So don't handle it at all.
 
+        // Seed users first, so that partial presence users can be filled in below.
+        for user in ready.relationships.iter().filter_map(|r| r.user.as_ref()) {
+            cache.update_user_entry(user);
+        }
+
         for (user_id, presence) in &mut ready.presences {
             if let Some(user) = presence.user.to_user() {
                 cache.update_user_entry(&user);
             }
             if let Some(user) = cache.user(user_id) {
-                presence.user.update_with_user(user);
+                let mut cached = PresenceUser::from(user);
+                cached.patch_from(&presence.user);
+                presence.user.overwrite_from(cached);
             }
 
-            cache.presences.insert(*user_id, presence.clone());
+            cache.insert_presence(presence);
         }
 
         *cache.shard_count.write() = ready.shard.map_or(1, |s| s[1]);
@@ -34,8 +34,10 @@ use std::collections::hash_map::RandomState;
 use std::collections::{HashMap, VecDeque};
 use std::hash::BuildHasher;
 use std::str::FromStr;
+use std::sync::Arc;
 #[cfg(feature = "temp_cache")]
 use std::time::Duration;
+use std::time::Instant;
 
 use dashmap::iter::Iter;
 use dashmap::mapref::entry::Entry;
@@ -148,6 +150,19 @@ pub struct Cache {
     /// status updates are often "eaten" by the gateway, and this should not
     /// be treated as being entirely 100% accurate.
     pub(crate) presences: DashMap<UserId, Presence>,
+    /// A map of users' presences in their compact form, used instead of
+    /// [`Self::presences`] when [`Settings::compact_presences`] is enabled.
+    pub(crate) compact_presences: DashMap<UserId, CompactPresence>,
+    /// The interned activity names shared by [`Self::compact_presences`].
+    activity_names: DashSet<Arc<str>>,
+    /// A map of users to the guild their most recent presence update came
+    /// from.
+    pub(crate) presence_guilds: DashMap<UserId, GuildId>,
+    /// A map of users to their last received status, and when it changed.
+    ///
+    /// Entries are removed once the user shares no cached guild with the
+    /// current user, and has no presence outside of guilds.
+    pub(crate) status_changes: DashMap<UserId, (OnlineStatus, Instant)>,
     /// A map of direct message channels that the current user has open with
     /// other users.
     pub(crate) private_channels: DashMap<ChannelId, PrivateChannel>,
@@ -906,6 +921,165 @@ impl Cache {
         self.users.get(&user_id).map(|u| u.clone())
     }
 
+    /// Retrieves a clone of a user's presence, as last received from the
+    /// gateway.
+    ///
+    /// This includes the presences received in the [`Ready`] event.
+    ///
+    /// [`Ready`]: crate::model::gateway::Ready
+    ///
+    /// If [`Settings::compact_presences`] is enabled, the presence is rebuilt
+    /// from its compact form, and is only approximate. Refer to
+    /// [`CompactPresence::to_presence`] for more information.
+    pub fn presence<U: Into<UserId>>(&self, user_id: U) -> Option<Presence> {
+        let user_id = user_id.into();
+
+        self.presences
+            .get(&user_id)
+            .map(|p| p.clone())
+            .or_else(|| self.compact_presences.get(&user_id).map(|p| p.to_presence()))
+    }
+
+    /// Retrieves the Id of the guild a user's most recent presence update
+    /// came from.
+    ///
+    /// This avoids searching every cached guild for the user's presence. The
+    /// guild may no longer be cached, such as after leaving it. This is [`None`]
+    /// if the most recent update was received outside of a guild.
+    #[inline]
+    pub fn presence_guild<U: Into<UserId>>(&self, user_id: U) -> Option<GuildId> {
+        self.presence_guilds.get(&user_id.into()).map(|g| *g)
+    }
+
+    /// Retrieves when a change of a user's status, such as from [`Online`] to
+    /// [`Idle`], was last received.
+    ///
+    /// This is the time the presence update was processed, not when the user
+    /// actually changed their status, and only transitions seen since the
+    /// cache was created are tracked. A user without a known previous presence
+    /// is considered to have been [`Offline`]. Users are forgotten once they
+    /// share no cached guild with the current user, and have no presence
+    /// outside of guilds.
+    ///
+    /// [`Online`]: crate::model::user::OnlineStatus::Online
+    /// [`Idle`]: crate::model::user::OnlineStatus::Idle
+    /// [`Offline`]: crate::model::user::OnlineStatus::Offline
+    #[inline]
+    pub fn user_status_since<U: Into<UserId>>(&self, user_id: U) -> Option<Instant> {
+        self.status_changes.get(&user_id.into()).map(|change| change.1)
+    }
+
+    /// Retrieves a clone of a user's presence in a guild, as last received
+    /// from the gateway.
+    ///
+    /// Unlike [`Self::presence`], this only looks up the presences stored in
+    /// the guild, and returns `None` if the guild is not cached.
+    pub fn guild_presence<G, U>(&self, guild_id: G, user_id: U) -> Option<Presence>
+    where
+        G: Into<GuildId>,
+        U: Into<UserId>,
+    {
+        self.guilds.get(&guild_id.into())?.presences.get(&user_id.into()).cloned()
+    }
+
+    /// Retrieves a clone of a user's presence in its compact form.
+    ///
+    /// This is only available if [`Settings::compact_presences`] is enabled.
+    #[inline]
+    pub fn compact_presence<U: Into<UserId>>(&self, user_id: U) -> Option<CompactPresence> {
+        self.compact_presences.get(&user_id.into()).map(|p| p.clone())
+    }
+
+    /// Clones all presences and returns them.
+    ///
+    /// This only includes presences received outside of a guild. If
+    /// [`Settings::compact_presences`] is enabled, these are stored in compact
+    /// form instead, and this returns an empty map: use
+    /// [`Self::compact_presence`], [`Self::presence`] or
+    /// [`Self::aggregated_presences`] instead.
+    #[inline]
+    pub fn presences(&self) -> DashMap<UserId, Presence> {
+        self.presences.clone()
+    }
+
+    /// Merges the presences received on every shard into a single map.
+    ///
+    /// Presences received outside of a guild, such as those of friends, are
+    /// stored once for all shards, while presences scoped to a guild are
+    /// stored in that guild, which belongs to a single shard. When a user's
+    /// presence is stored in several places, the one from their most recent
+    /// presence update wins, as tracked by [`Self::presence_guild`]. A user
+    /// whose most recent update went offline in a guild is left out, and
+    /// presences from other guilds are only used as a fallback.
+    ///
+    /// If [`Settings::compact_presences`] is enabled, presences received
+    /// outside of a guild are rebuilt from their compact form, like in
+    /// [`Self::presence`].
+    pub fn aggregated_presences(&self) -> HashMap<UserId, Presence> {
+        let mut presences = HashMap::new();
+        let mut fallbacks = HashMap::new();
+
+        for guild in &self.guilds {
+            for (user_id, presence) in &guild.presences {
+                if self.presence_guild(*user_id) == Some(guild.id) {
+                    presences.insert(*user_id, presence.clone());
+                } else {
+                    fallbacks.entry(*user_id).or_insert_with(|| presence.clone());
+                }
+            }
+        }
+
+        let global = self
+            .presences
+            .iter()
+            .map(|p| p.clone())
+            .chain(self.compact_presences.iter().map(|p| p.to_presence()));
+        for presence in global {
+            let user_id = presence.user.id;
+
+            // The most recent update came from a guild, which takes precedence.
+            if self.presence_guilds.contains_key(&user_id) {
+                fallbacks.entry(user_id).or_insert(presence);
+            } else {
+                presences.insert(user_id, presence);
+            }
+        }
+
+        for (user_id, presence) in fallbacks {
+            let went_offline = matches!(
+                self.presence_guild(user_id),
+                Some(guild_id) if self.guilds.contains_key(&guild_id)
+            );
+
+            if !went_offline {
+                presences.entry(user_id).or_insert(presence);
+            }
+        }
+
+        presences
+    }
+
+    /// Clones every cached presence, sorted by user Id, such as to serialize
+    /// them for offline analysis.
+    ///
+    /// The presences are merged like in [`Self::aggregated_presences`].
+    ///
+    /// **Note**: This is not a consistent snapshot. The cache has no lock
+    /// covering all presences, so presence updates received while these are
+    /// cloned may or may not be included. Each presence is cloned as a whole,
+    /// so none is torn.
+    ///
+    /// **Note**: Every presence is cloned, which temporarily doubles the
+    /// memory used by presences. With large guilds or friend lists, this can
+    /// amount to several megabytes.
+    pub fn export_presences(&self) -> Vec<Presence> {
+        let mut presences: Vec<_> =
+            self.aggregated_presences().into_iter().map(|(_, p)| p).collect();
+        presences.sort_unstable_by_key(|presence| presence.user.id);
+
+        presences
+    }
+
     /// Clones all users and returns them.
     #[inline]
     pub fn users(&self) -> DashMap<UserId, User> {
@@ -1008,6 +1182,67 @@ impl Cache {
             },
         }
     }
+
+    /// Stores a presence, in compact form if enabled in the settings.
+    pub(crate) fn insert_presence(&self, presence: &Presence) {
+        let user_id = presence.user.id;
+        self.presence_guilds.remove(&user_id);
+
+        if self.settings.read().compact_presences {
+            let compact = CompactPresence::new(presence, |name| self.intern_activity_name(name));
+            if let Some(old) = self.compact_presences.insert(user_id, compact) {
+                self.release_activity_names(old);
+            }
+        } else {
+            self.presences.insert(user_id, presence.clone());
+        }
+    }
+
+    pub(crate) fn remove_presence(&self, user_id: UserId) {
+        self.presence_guilds.remove(&user_id);
+        self.presences.remove(&user_id);
+        if let Some((_, old)) = self.compact_presences.remove(&user_id) {
+            self.release_activity_names(old);
+        }
+    }
+
+    /// Forgets when a user's status last changed, unless they still share a
+    /// cached guild with the current user or have a presence outside of
+    /// guilds.
+    pub(crate) fn prune_status_change(&self, user_id: UserId) {
+        let known = self.presences.contains_key(&user_id)
+            || self.compact_presences.contains_key(&user_id)
+            || self.guilds.iter().any(|guild| guild.members.contains_key(&user_id));
+
+        if !known {
+            self.status_changes.remove(&user_id);
+        }
+    }
+
+    fn intern_activity_name(&self, name: &str) -> Arc<str> {
+        if let Some(interned) = self.activity_names.get(name) {
+            return Arc::clone(&interned);
+        }
+
+        let interned: Arc<str> = Arc::from(name);
+        self.activity_names.insert(Arc::clone(&interned));
+
+        interned
+    }
+
+    /// Forgets the interned activity names of a removed compact presence
+    /// which are no longer used by any other.
+    ///
+    /// Names still referenced elsewhere, such as by a clone returned from
+    /// [`Self::compact_presence`], are kept until released again.
+    fn release_activity_names(&self, presence: CompactPresence) {
+        for activity in presence.activities {
+            // Only this activity and the interner hold the name.
+            if Arc::strong_count(&activity.name) <= 2 {
+                self.activity_names.remove(&*activity.name);
+            }
+        }
+    }
 }
 
 impl Default for Cache {
@@ -1020,6 +1255,10 @@ impl Default for Cache {
             guilds: DashMap::default(),
             messages: DashMap::default(),
             presences: DashMap::default(),
+            compact_presences: DashMap::default(),
+            activity_names: DashSet::default(),
+            presence_guilds: DashMap::default(),
+            status_changes: DashMap::default(),
             private_channels: DashMap::with_capacity(128),
             settings: RwLock::new(Settings::default()),
             shard_count: RwLock::new(1),
@@ -1038,9 +1277,19 @@ mod test {
     use std::collections::HashMap;
 
     use crate::cache::{Cache, CacheUpdate, Settings};
-    use crate::json::from_number;
+    use crate::json::{self, from_number, json, Value};
+    use crate::model::gateway::ready_json;
+    use crate::model::guild::guild_json;
     use crate::model::prelude::*;
 
+    fn guild_create(guild_id: u64, members: Vec<Value>) -> GuildCreateEvent {
+        let mut value = guild_json(guild_id);
+        value["member_count"] = json!(members.len());
+        value["members"] = json!(members);
+
+        json::from_value(value).unwrap()
+    }
+
     #[test]
     fn test_cache_messages() {
         let mut settings = Settings::new();
@@ -1233,4 +1482,232 @@ mod test {
         // Assert that the channel's message cache no longer exists.
         assert!(!cache.messages.contains_key(&ChannelId(2)));
     }
+
+    #[test]
+    fn test_cache_presences_from_ready() {
+        let cache = Cache::default();
+
+        let mut value = ready_json();
+        value["presences"] = json!([{
+            "user": {"id": "2"},
+            "status": "idle",
+            "activities": [{"type": 0, "name": "Game"}],
+        }]);
+        value["relationships"] = json!([{
+            "id": "2",
+            "type": 1,
+            "user": {"id": "2", "username": "friend", "discriminator": "0001"},
+        }]);
+        let mut ready: ReadyEvent = json::from_value(value).unwrap();
+
+        assert!(cache.update(&mut ready).is_none());
+
+        let presence = cache.presence(UserId(2)).unwrap();
+        assert_eq!(presence.status, OnlineStatus::Idle);
+        assert_eq!(presence.activities[0].name, "Game");
+        // The partial presence user is filled in from the relationship's user.
+        assert_eq!(presence.user.name.as_deref(), Some("friend"));
+    }
+
+    #[test]
+    fn test_cache_compact_presences() {
+        let mut settings = Settings::new();
+        settings.compact_presences(true);
+        let cache = Cache::new_with_settings(settings);
+
+        for id in 1..=2 {
+            let mut event: PresencesReplaceEvent = json::from_value(json!([{
+                "user": {"id": id.to_string(), "username": "user"},
+                "status": "online",
+                "activities": [{"type": 0, "name": "Game", "state": "In a match"}],
+            }]))
+            .unwrap();
+            cache.update(&mut event);
+        }
+
+        let first = cache.compact_presence(UserId(1)).unwrap();
+        let second = cache.compact_presence(UserId(2)).unwrap();
+        assert!(std::sync::Arc::ptr_eq(&first.activities[0].name, &second.activities[0].name));
+
+        let presence = cache.presence(UserId(1)).unwrap();
+        assert_eq!(presence.status, OnlineStatus::Online);
+        assert_eq!(presence.activities[0].name, "Game");
+        assert_eq!(presence.activities[0].state.as_deref(), Some("In a match"));
+        // User data beyond the Id is not kept.
+        assert_eq!(presence.user.name, None);
+        assert!(cache.presences.is_empty());
+    }
+
+    #[test]
+    fn test_cache_compact_presences_release_names() {
+        let mut settings = Settings::new();
+        settings.compact_presences(true);
+        let cache = Cache::new_with_settings(settings);
+
+        let update = |id: u64, name: &str| {
+            let mut event: PresencesReplaceEvent = json::from_value(json!([{
+                "user": {"id": id.to_string()},
+                "status": "online",
+                "activities": [{"type": 0, "name": name}],
+            }]))
+            .unwrap();
+            cache.update(&mut event);
+        };
+
+        update(1, "Game");
+        update(2, "Game");
+        assert_eq!(cache.activity_names.len(), 1);
+
+        // The name is kept while another presence uses it.
+        update(1, "Other game");
+        assert_eq!(cache.activity_names.len(), 2);
+
+        update(2, "Other game");
+        assert_eq!(cache.activity_names.len(), 1);
+        assert!(!cache.activity_names.contains("Game"));
+
+        cache.remove_presence(UserId(1));
+        cache.remove_presence(UserId(2));
+        assert!(cache.activity_names.is_empty());
+    }
+
+    #[test]
+    fn test_cache_presence_guild() {
+        let cache = Cache::default();
+        assert_eq!(cache.presence_guild(UserId(1)), None);
+
+        for guild_id in ["2", "3"] {
+            let mut event: PresenceUpdateEvent = json::from_value(json!({
+                "user": {"id": "1"},
+                "status": "online",
+                "guild_id": guild_id,
+            }))
+            .unwrap();
+            cache.update(&mut event);
+        }
+
+        assert_eq!(cache.presence_guild(UserId(1)), Some(GuildId(3)));
+    }
+
+    #[test]
+    fn test_cache_user_status_since() {
+        let cache = Cache::default();
+        assert_eq!(cache.user_status_since(UserId(1)), None);
+
+        let update = |status: &str| {
+            let mut event: PresenceUpdateEvent = json::from_value(json!({
+                "user": {"id": "1"},
+                "status": status,
+            }))
+            .unwrap();
+            cache.update(&mut event);
+            cache.user_status_since(UserId(1))
+        };
+
+        let online = update("online").unwrap();
+        // The status did not change.
+        assert_eq!(update("online"), Some(online));
+        let idle = update("idle").unwrap();
+        assert!(idle >= online);
+        let offline = update("offline").unwrap();
+        assert!(offline >= idle);
+        // No presence is cached while offline, so there is no transition.
+        assert_eq!(update("offline"), Some(offline));
+    }
+
+    #[test]
+    fn test_cache_user_status_since_guilds() {
+        let cache = Cache::default();
+        let member = json!({
+            "user": {"id": "1", "username": "user", "discriminator": "0001"},
+            "roles": [],
+            "joined_at": "2022-01-01T00:00:00Z",
+            "deaf": false,
+            "mute": false,
+        });
+        cache.update(&mut guild_create(2, vec![member.clone()]));
+        cache.update(&mut guild_create(3, vec![member]));
+
+        let update = |status: &str, guild_id: &str| {
+            let mut event: PresenceUpdateEvent = json::from_value(json!({
+                "user": {"id": "1"},
+                "status": status,
+                "guild_id": guild_id,
+            }))
+            .unwrap();
+            cache.update(&mut event);
+            cache.user_status_since(UserId(1))
+        };
+
+        let online = update("online", "2").unwrap();
+        // The same status, received from another guild, is not a change.
+        assert_eq!(update("online", "3"), Some(online));
+        let idle = update("idle", "3").unwrap();
+        assert!(idle >= online);
+        assert_eq!(update("idle", "2"), Some(idle));
+        // Changes are tracked for guilds which are not cached too.
+        let dnd = update("dnd", "4").unwrap();
+        assert!(dnd >= idle);
+
+        // The user is forgotten once they share no cached guild.
+        let remove = |guild_id: &str| {
+            let mut event: GuildMemberRemoveEvent = json::from_value(json!({
+                "guild_id": guild_id,
+                "user": {"id": "1", "username": "user", "discriminator": "0001"},
+            }))
+            .unwrap();
+            cache.update(&mut event);
+        };
+        remove("2");
+        assert_eq!(cache.user_status_since(UserId(1)), Some(dnd));
+
+        let mut guild_delete: GuildDeleteEvent =
+            json::from_value(json!({"id": "3", "unavailable": false})).unwrap();
+        cache.update(&mut guild_delete);
+        assert_eq!(cache.user_status_since(UserId(1)), None);
+    }
+
+    #[test]
+    fn test_cache_aggregated_presences() {
+        let cache = Cache::default();
+
+        for (guild_id, shard) in [(1_u64, 0_u64), (1 << 22, 1)] {
+            cache.update(&mut guild_create(guild_id, vec![]));
+            assert_eq!(crate::utils::shard_id(guild_id, 2), shard);
+        }
+
+        let updates = [
+            // Seen in both guilds, most recently in the second one.
+            json!({"user": {"id": "3"}, "status": "idle", "guild_id": "1"}),
+            json!({"user": {"id": "3"}, "status": "dnd", "guild_id": "4194304"}),
+            // Seen in a guild, then as a friend.
+            json!({"user": {"id": "4"}, "status": "idle", "guild_id": "1"}),
+            json!({"user": {"id": "4"}, "status": "online"}),
+            // Seen as a friend, then went offline in a guild.
+            json!({"user": {"id": "5"}, "status": "online"}),
+            json!({"user": {"id": "5"}, "status": "offline", "guild_id": "4194304"}),
+        ];
+        for update in updates {
+            let mut event: PresenceUpdateEvent = json::from_value(update).unwrap();
+            cache.update(&mut event);
+        }
+
+        let presences = cache.aggregated_presences();
+        assert_eq!(presences.len(), 2);
+        assert_eq!(presences[&UserId(3)].status, OnlineStatus::DoNotDisturb);
+        assert_eq!(presences[&UserId(4)].status, OnlineStatus::Online);
+        assert!(!presences.contains_key(&UserId(5)));
+
+        let status = |guild_id: u64| cache.guild_presence(guild_id, 3).map(|p| p.status);
+        assert_eq!(status(1), Some(OnlineStatus::Idle));
+        assert_eq!(status(1 << 22), Some(OnlineStatus::DoNotDisturb));
+        assert_eq!(status(2), None);
+
+        let exported: Vec<_> =
+            cache.export_presences().iter().map(|p| (p.user.id, p.status)).collect();
+        assert_eq!(exported, vec![
+            (UserId(3), OnlineStatus::DoNotDisturb),
+            (UserId(4), OnlineStatus::Online),
+        ]);
+    }
 }
@@ -17,6 +17,16 @@ pub struct Settings {
     ///
     /// Defaults to 0.
     pub max_messages: usize,
+    /// Whether to store presences in their compact form, as a
+    /// [`CompactPresence`], instead of in full.
+    ///
+    /// When enabled, [`Cache::presences`] is always empty.
+    ///
+    /// Defaults to false.
+    ///
+    /// [`CompactPresence`]: crate::model::gateway::CompactPresence
+    /// [`Cache::presences`]: super::Cache::presences
+    pub compact_presences: bool,
 }
 
 impl Settings {
@@ -48,4 +58,15 @@ impl Settings {
 
         self
     }
+
+    /// Sets whether presences are stored in their compact form.
+    ///
+    /// Refer to [`compact_presences`] for more information.
+    ///
+    /// [`compact_presences`]: #structfield.compact_presences
+    pub fn compact_presences(&mut self, compact: bool) -> &mut Self {
+        self.compact_presences = compact;
+
+        self
+    }
 }
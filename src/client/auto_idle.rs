@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use super::bridge::gateway::{ShardId, ShardRunnerInfo};
+use crate::model::user::OnlineStatus;
+
+/// How often the idle timer is checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// The minimum time between two status updates, to respect the gateway's
+/// presence update ratelimit.
+const MIN_UPDATE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Automatically sets the current user's status after a period of inactivity,
+/// like the official client's auto-away.
+///
+/// Once no activity was recorded through [`Self::touch`] for the configured
+/// threshold, the status is set to [`Idle`], or the one given to
+/// [`Self::status`]. The status each shard had before going idle is restored
+/// on the next activity, defaulting to [`Online`] for shards started since.
+///
+/// Status updates are debounced, so that quick transitions back and forth do
+/// not hit the presence update ratelimit.
+///
+/// Clones share the same idle timer, so a clone can be kept to call
+/// [`Self::touch`] after passing one to [`ClientBuilder::auto_idle`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::time::Duration;
+///
+/// use serenity::client::AutoIdle;
+/// use serenity::prelude::*;
+///
+/// struct Handler;
+///
+/// impl EventHandler for Handler {}
+///
+/// let auto_idle = AutoIdle::new(Duration::from_secs(10 * 60));
+///
+/// let token = std::env::var("DISCORD_TOKEN")?;
+/// let mut client =
+///     Client::builder(&token).event_handler(Handler).auto_idle(auto_idle.clone()).await?;
+///
+/// // Somewhere the user is active:
+/// auto_idle.touch();
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// [`Idle`]: OnlineStatus::Idle
+/// [`Online`]: OnlineStatus::Online
+/// [`ClientBuilder::auto_idle`]: super::ClientBuilder::auto_idle
+#[derive(Clone, Debug)]
+pub struct AutoIdle {
+    threshold: Duration,
+    status: OnlineStatus,
+    last_activity: Arc<StdMutex<Instant>>,
+}
+
+impl AutoIdle {
+    /// Creates a new auto-away, going idle after `threshold` without activity.
+    #[must_use]
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            status: OnlineStatus::Idle,
+            last_activity: Arc::new(StdMutex::new(Instant::now())),
+        }
+    }
+
+    /// Sets the status to use while idle.
+    ///
+    /// Defaults to [`OnlineStatus::Idle`].
+    #[must_use]
+    pub fn status(mut self, status: OnlineStatus) -> Self {
+        self.status = status;
+
+        self
+    }
+
+    /// Records activity, resetting the idle timer.
+    pub fn touch(&self) {
+        *self.lock() = Instant::now();
+    }
+
+    /// Whether no activity was recorded for at least the threshold.
+    #[must_use]
+    pub fn is_idle(&self) -> bool {
+        self.lock().elapsed() >= self.threshold
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Instant> {
+        // The guarded `Instant` can not be left in an invalid state.
+        self.last_activity.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Sets the status of every running shard on idle transitions, until the
+    /// returned future is dropped.
+    pub(crate) async fn run(self, runners: Arc<Mutex<HashMap<ShardId, ShardRunnerInfo>>>) {
+        let mut idle = false;
+        let mut last_update: Option<Instant> = None;
+        let mut previous = HashMap::new();
+
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+
+            let now_idle = self.is_idle();
+            if now_idle == idle
+                || matches!(last_update, Some(at) if at.elapsed() < MIN_UPDATE_INTERVAL)
+            {
+                continue;
+            }
+
+            idle = now_idle;
+            last_update = Some(Instant::now());
+
+            self.set_statuses(idle, &*runners.lock().await, &mut previous);
+        }
+    }
+
+    /// Sets the status of every shard when going idle, remembering the
+    /// statuses to restore when back from idle.
+    fn set_statuses(
+        &self,
+        idle: bool,
+        runners: &HashMap<ShardId, ShardRunnerInfo>,
+        previous: &mut HashMap<ShardId, OnlineStatus>,
+    ) {
+        if idle {
+            debug!("Auto-idle: setting status to {:?}", self.status);
+
+            *previous = runners.iter().map(|(id, info)| (*id, info.status)).collect();
+
+            for info in runners.values() {
+                info.runner_tx.set_status(self.status);
+            }
+        } else {
+            debug!("Auto-idle: restoring statuses");
+
+            for (id, info) in runners {
+                let status = previous.remove(id).unwrap_or(OnlineStatus::Online);
+                info.runner_tx.set_status(status);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+
+    use super::AutoIdle;
+    use crate::client::bridge::gateway::{
+        ShardClientMessage,
+        ShardId,
+        ShardMessenger,
+        ShardRunnerInfo,
+        ShardRunnerMessage,
+    };
+    use crate::gateway::{CommandRatelimiter, ConnectionStage, InterMessage};
+    use crate::model::user::OnlineStatus;
+
+    fn runner_info(status: OnlineStatus) -> (ShardRunnerInfo, UnboundedReceiver<InterMessage>) {
+        let (tx, rx) = unbounded();
+        let info = ShardRunnerInfo {
+            latency: None,
+            heartbeat_interval: None,
+            heartbeat_failures: 0,
+            command_ratelimiter: CommandRatelimiter::default(),
+            runner_tx: ShardMessenger::new(tx),
+            stage: ConnectionStage::Connected,
+            session_state: None,
+            status,
+        };
+
+        (info, rx)
+    }
+
+    fn sent_status(rx: &mut UnboundedReceiver<InterMessage>) -> Option<OnlineStatus> {
+        match rx.try_recv() {
+            Ok(InterMessage::Client(message)) => match *message {
+                ShardClientMessage::Runner(ShardRunnerMessage::SetStatus(status)) => Some(status),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_auto_idle_touch() {
+        let auto_idle = AutoIdle::new(Duration::from_secs(60));
+        assert!(!auto_idle.is_idle());
+
+        let mut last_activity = auto_idle.lock();
+        *last_activity = last_activity.checked_sub(Duration::from_secs(61)).unwrap();
+        drop(last_activity);
+        assert!(auto_idle.is_idle());
+
+        // Clones share the idle timer.
+        auto_idle.clone().touch();
+        assert!(!auto_idle.is_idle());
+    }
+
+    #[test]
+    fn test_auto_idle_restores_status() {
+        let auto_idle = AutoIdle::new(Duration::from_secs(60));
+        let (dnd, mut dnd_rx) = runner_info(OnlineStatus::DoNotDisturb);
+        let (online, mut online_rx) = runner_info(OnlineStatus::Online);
+        let mut runners = HashMap::new();
+        runners.insert(ShardId(0), dnd);
+        runners.insert(ShardId(1), online);
+        let mut previous = HashMap::new();
+
+        auto_idle.set_statuses(true, &runners, &mut previous);
+        assert_eq!(sent_status(&mut dnd_rx), Some(OnlineStatus::Idle));
+        assert_eq!(sent_status(&mut online_rx), Some(OnlineStatus::Idle));
+
+        // A shard started while idle goes online.
+        let (started, mut started_rx) = runner_info(OnlineStatus::Idle);
+        runners.insert(ShardId(2), started);
+
+        auto_idle.set_statuses(false, &runners, &mut previous);
+        assert_eq!(sent_status(&mut dnd_rx), Some(OnlineStatus::DoNotDisturb));
+        assert_eq!(sent_status(&mut online_rx), Some(OnlineStatus::Online));
+        assert_eq!(sent_status(&mut started_rx), Some(OnlineStatus::Online));
+    }
+}
@@ -2,12 +2,13 @@
 //! itself.
 
 use super::ShardId;
-use crate::gateway::ConnectionStage;
+use crate::gateway::{ConnectionStage, ReconnectType};
 
 #[allow(clippy::enum_variant_names)]
 #[derive(Clone, Debug)]
 pub(crate) enum ClientEvent {
     ShardStageUpdate(ShardStageUpdateEvent),
+    ShardReconnect(ShardReconnectEvent),
 }
 
 /// An event denoting that a shard's connection stage was changed.
@@ -25,3 +26,21 @@ pub struct ShardStageUpdateEvent {
     /// The ID of the shard that had its connection stage change.
     pub shard_id: ShardId,
 }
+
+/// An event denoting that a shard is reconnecting to the gateway.
+///
+/// Once reconnected, a [`Ready`] event follows a reidentify, and a
+/// [`Resumed`] event follows a resume.
+///
+/// [`Ready`]: crate::model::event::Event::Ready
+/// [`Resumed`]: crate::model::event::Event::Resumed
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ShardReconnectEvent {
+    /// Whether the shard resumes its session or starts a new one.
+    pub kind: ReconnectType,
+    /// The close code sent by the gateway that caused the reconnect, if any.
+    pub close_code: Option<u16>,
+    /// The ID of the shard that is reconnecting.
+    pub shard_id: ShardId,
+}
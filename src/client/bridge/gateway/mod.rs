@@ -54,13 +54,14 @@ mod shard_runner_message;
 use std::fmt;
 use std::time::Duration as StdDuration;
 
-pub use self::shard_manager::{ShardManager, ShardManagerOptions};
+pub use self::shard_manager::{ShardManager, ShardManagerOptions, ShardManagerSettings};
 pub use self::shard_manager_monitor::{ShardManagerError, ShardManagerMonitor};
 pub use self::shard_messenger::ShardMessenger;
 pub use self::shard_queuer::ShardQueuer;
-pub use self::shard_runner::{ShardRunner, ShardRunnerOptions};
+pub use self::shard_runner::{ShardRunner, ShardRunnerOptions, ShardRunnerSettings};
 pub use self::shard_runner_message::{ChunkGuildFilter, ShardRunnerMessage};
-use crate::gateway::ConnectionStage;
+use crate::gateway::{CommandRatelimiter, ConnectionStage, SessionState};
+use crate::model::user::OnlineStatus;
 
 /// A message either for a [`ShardManager`] or a [`ShardRunner`].
 // Once we can use `Box` as part of a pattern, we will reconsider boxing.
@@ -79,7 +80,16 @@ pub enum ShardManagerMessage {
     /// Indicator that a [`ShardManagerMonitor`] should restart a shard.
     Restart(ShardId),
     /// An update from a shard runner,
-    ShardUpdate { id: ShardId, latency: Option<StdDuration>, stage: ConnectionStage },
+    #[non_exhaustive]
+    ShardUpdate {
+        id: ShardId,
+        latency: Option<StdDuration>,
+        heartbeat_interval: Option<StdDuration>,
+        heartbeat_failures: u64,
+        stage: ConnectionStage,
+        session_state: Option<SessionState>,
+        status: OnlineStatus,
+    },
     /// Indicator that a [`ShardManagerMonitor`] should fully shutdown a shard
     /// without bringing it back up.
     Shutdown(ShardId, u16),
@@ -141,15 +151,34 @@ impl fmt::Display for ShardId {
 /// The [`ShardId`] is not included because, as it stands, you probably already
 /// know the Id if you obtained this.
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct ShardRunnerInfo {
     /// The latency between when a heartbeat was sent and when the
     /// acknowledgement was received.
     pub latency: Option<StdDuration>,
+    /// The heartbeat interval received from the gateway in its HELLO payload.
+    pub heartbeat_interval: Option<StdDuration>,
+    /// The number of heartbeats the shard could not send, such as because
+    /// the connection was lost.
+    ///
+    /// This is reset when the shard is restarted.
+    pub heartbeat_failures: u64,
+    /// The budget of commands the shard can send over the gateway, shared
+    /// with the shard.
+    pub command_ratelimiter: CommandRatelimiter,
     /// The channel used to communicate with the shard runner, telling it
     /// what to do with regards to its status.
     pub runner_tx: ShardMessenger,
     /// The current connection stage of the shard.
     pub stage: ConnectionStage,
+    /// The state needed to resume the shard's session, if it has one.
+    ///
+    /// This is updated along with the other fields, such as when a heartbeat
+    /// is acknowledged, so the sequence number may lag behind. Resuming from
+    /// an older sequence number only replays more events.
+    pub session_state: Option<SessionState>,
+    /// The online status set on the shard's presence.
+    pub status: OnlineStatus,
 }
 
 impl AsRef<ShardMessenger> for ShardRunnerInfo {
@@ -157,3 +186,13 @@ impl AsRef<ShardMessenger> for ShardRunnerInfo {
         &self.runner_tx
     }
 }
+
+/// A framework which ignores every message, for tests running shards.
+#[cfg(all(test, feature = "framework"))]
+pub(crate) struct NoFramework;
+
+#[cfg(all(test, feature = "framework"))]
+#[async_trait::async_trait]
+impl crate::framework::Framework for NoFramework {
+    async fn dispatch(&self, _: crate::client::Context, _: crate::model::channel::Message) {}
+}
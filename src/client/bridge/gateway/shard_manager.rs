@@ -1,4 +1,4 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 
 use futures::channel::mpsc::{self, UnboundedReceiver as Receiver, UnboundedSender as Sender};
@@ -21,8 +21,10 @@ use crate::client::bridge::voice::VoiceGatewayManager;
 use crate::client::{EventHandler, RawEventHandler};
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
+use crate::gateway::{GatewayConfig, SessionState};
 use crate::internal::prelude::*;
 use crate::internal::tokio::spawn_named;
+use crate::model::id::UserId;
 use crate::CacheAndHttp;
 
 /// A manager for handling the status of shards by starting them, restarting
@@ -50,7 +52,11 @@ use crate::CacheAndHttp;
 /// use std::env;
 /// use std::sync::Arc;
 ///
-/// use serenity::client::bridge::gateway::{ShardManager, ShardManagerOptions};
+/// use serenity::client::bridge::gateway::{
+///     ShardManager,
+///     ShardManagerOptions,
+///     ShardManagerSettings,
+/// };
 /// use serenity::client::{EventHandler, RawEventHandler};
 /// use serenity::framework::{Framework, StandardFramework};
 /// use serenity::http::Http;
@@ -76,6 +82,7 @@ use crate::CacheAndHttp;
 ///     data: &data,
 ///     event_handler: &Some(event_handler),
 ///     raw_event_handler: &None,
+///     settings: ShardManagerSettings::default(),
 ///     framework: &framework,
 ///     // the shard index to start initiating from
 ///     shard_index: 0,
@@ -127,9 +134,11 @@ impl ShardManager {
             data: Arc::clone(opt.data),
             event_handler: opt.event_handler.as_ref().map(Arc::clone),
             raw_event_handler: opt.raw_event_handler.as_ref().map(Arc::clone),
+            settings: opt.settings,
             #[cfg(feature = "framework")]
             framework: Arc::clone(opt.framework),
             last_start: None,
+            failed_starts: 0,
             manager_tx: thread_tx.clone(),
             queue: VecDeque::new(),
             runners: Arc::clone(&runners),
@@ -256,6 +265,24 @@ impl ShardManager {
         self.runners.lock().await.keys().copied().collect()
     }
 
+    /// Returns the state needed to resume the session of each shard that has
+    /// one, by shard Id.
+    ///
+    /// These can be persisted, e.g. before the process exits, and passed to
+    /// [`ClientBuilder::resume_session`] on the next launch. Refer to
+    /// [`SessionState`] for how long a session can be resumed.
+    ///
+    /// [`ClientBuilder::resume_session`]: crate::client::ClientBuilder::resume_session
+    #[instrument(skip(self))]
+    pub async fn session_states(&self) -> HashMap<u64, SessionState> {
+        self.runners
+            .lock()
+            .await
+            .iter()
+            .filter_map(|(id, runner)| runner.session_state.clone().map(|state| (id.0, state)))
+            .collect()
+    }
+
     /// Attempts to shut down the shard runner by Id.
     ///
     /// Returns a boolean indicating whether a shard runner was present. This is
@@ -346,6 +373,7 @@ pub struct ShardManagerOptions<'a> {
     pub data: &'a Arc<RwLock<TypeMap>>,
     pub event_handler: &'a Option<Arc<dyn EventHandler>>,
     pub raw_event_handler: &'a Option<Arc<dyn RawEventHandler>>,
+    pub settings: ShardManagerSettings,
     #[cfg(feature = "framework")]
     pub framework: &'a Arc<dyn Framework + Send + Sync>,
     pub shard_index: u64,
@@ -356,3 +384,19 @@ pub struct ShardManagerOptions<'a> {
     pub ws_url: &'a Arc<Mutex<String>>,
     pub cache_and_http: &'a Arc<CacheAndHttp>
 }
+
+/// Optional settings of the shards started by a [`ShardManager`].
+///
+/// New settings may be added without it being a breaking change, so this is
+/// made with [`Default::default`], and the fields set afterwards.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct ShardManagerSettings {
+    /// The users whose presence updates are dispatched, if filtered.
+    pub presence_filter: Option<Arc<HashSet<UserId>>>,
+    /// The settings of the shards' connections.
+    pub gateway_config: GatewayConfig,
+    /// Previously persisted sessions to resume, by shard Id, instead of
+    /// identifying when the shards are first started.
+    pub resume_sessions: HashMap<u64, SessionState>,
+}
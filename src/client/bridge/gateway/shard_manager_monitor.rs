@@ -66,14 +66,22 @@ impl ShardManagerMonitor {
                 ShardManagerMessage::ShardUpdate {
                     id,
                     latency,
+                    heartbeat_interval,
+                    heartbeat_failures,
                     stage,
+                    session_state,
+                    status,
                 } => {
                     let manager = self.manager.lock().await;
                     let mut runners = manager.runners.lock().await;
 
                     if let Some(runner) = runners.get_mut(&id) {
                         runner.latency = latency;
+                        runner.heartbeat_interval = heartbeat_interval;
+                        runner.heartbeat_failures = heartbeat_failures;
                         runner.stage = stage;
+                        runner.session_state = session_state;
+                        runner.status = status;
                     }
                 },
                 ShardManagerMessage::Shutdown(shard_id, code) => {
@@ -10,7 +10,7 @@ use crate::collector::{
     ModalInteractionFilter,
     ReactionFilter,
 };
-use crate::gateway::InterMessage;
+use crate::gateway::{generate_nonce, InterMessage};
 use crate::model::prelude::*;
 
 /// A lightweight wrapper around an mpsc sender.
@@ -52,6 +52,11 @@ impl ShardMessenger {
     /// If the `cache` feature is enabled, the cache will automatically be
     /// updated with member chunks.
     ///
+    /// The chunks carry the request's `nonce`, which is returned so that they
+    /// can be matched to it. If no nonce is given, one is generated with
+    /// [`generate_nonce`]. A nonce must be at most [`MAX_NONCE_LENGTH`]
+    /// characters long, or Discord ignores it.
+    ///
     /// # Examples
     ///
     /// Chunk a single guild by Id, limiting to 2000 [`Member`]s, and not
@@ -104,19 +109,26 @@ impl ShardMessenger {
     /// #     Ok(())
     /// # }
     /// ```
+    ///
+    /// [`generate_nonce`]: crate::gateway::generate_nonce
+    /// [`MAX_NONCE_LENGTH`]: crate::gateway::MAX_NONCE_LENGTH
     pub fn chunk_guild(
         &self,
         guild_id: GuildId,
         limit: Option<u16>,
         filter: ChunkGuildFilter,
         nonce: Option<String>,
-    ) {
+    ) -> String {
+        let nonce = nonce.unwrap_or_else(generate_nonce);
+
         drop(self.send_to_shard(ShardRunnerMessage::ChunkGuild {
             guild_id,
             limit,
             filter,
-            nonce,
+            nonce: Some(nonce.clone()),
         }));
+
+        nonce
     }
 
     /// Sets the user's current activity, if any.
@@ -225,6 +237,29 @@ impl ShardMessenger {
         drop(self.send_to_shard(ShardRunnerMessage::SetStatus(online_status)));
     }
 
+    /// Requests the presences of all of a guild's members, which are sent
+    /// through `sender` once every member chunk identified by `nonce` has
+    /// been received.
+    ///
+    /// If the shard restarts before then, `sender` is dropped without a value
+    /// being sent.
+    ///
+    /// Refer to [`Shard::request_presences`] for more information.
+    ///
+    /// [`Shard::request_presences`]: crate::gateway::Shard::request_presences
+    pub fn request_presences(
+        &self,
+        guild_id: GuildId,
+        nonce: String,
+        sender: Sender<Vec<Presence>>,
+    ) {
+        drop(self.send_to_shard(ShardRunnerMessage::RequestPresences {
+            guild_id,
+            nonce,
+            sender,
+        }));
+    }
+
     /// Shuts down the websocket by attempting to cleanly close the
     /// connection.
     pub fn shutdown_clean(&self) {
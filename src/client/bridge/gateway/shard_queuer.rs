@@ -12,11 +12,13 @@ use super::{
     ShardClientMessage,
     ShardId,
     ShardManagerMessage,
+    ShardManagerSettings,
     ShardMessenger,
     ShardQueuerMessage,
     ShardRunner,
     ShardRunnerInfo,
     ShardRunnerOptions,
+    ShardRunnerSettings,
 };
 #[cfg(feature = "voice")]
 use crate::client::bridge::voice::VoiceGatewayManager;
@@ -29,6 +31,10 @@ use crate::internal::tokio::spawn_named;
 use crate::CacheAndHttp;
 
 const WAIT_BETWEEN_BOOTS_IN_SECONDS: u64 = 5;
+/// The longest wait between shard starts after consecutive failed starts.
+const MAX_WAIT_AFTER_FAILED_STARTS: Duration = Duration::from_secs(120);
+/// How long a fetched client build number is reused.
+const CLIENT_BUILD_NUMBER_MAX_AGE: Duration = Duration::from_secs(60 * 60);
 
 /// The shard queuer is a simple loop that runs indefinitely to manage the
 /// startup of shards.
@@ -36,6 +42,7 @@ const WAIT_BETWEEN_BOOTS_IN_SECONDS: u64 = 5;
 /// A shard queuer instance _should_ be run in its own thread, due to the
 /// blocking nature of the loop itself as well as a 5 second thread sleep
 /// between shard starts.
+#[non_exhaustive]
 pub struct ShardQueuer {
     /// A copy of [`Client::data`] to be given to runners for contextual
     /// dispatching.
@@ -52,6 +59,11 @@ pub struct ShardQueuer {
     ///
     /// [`Client`]: crate::Client
     pub raw_event_handler: Option<Arc<dyn RawEventHandler>>,
+    /// The optional settings of the shards.
+    ///
+    /// A persisted session is removed from these once its shard tried
+    /// resuming it.
+    pub settings: ShardManagerSettings,
     /// A copy of the framework
     #[cfg(feature = "framework")]
     pub framework: Arc<dyn Framework + Send + Sync>,
@@ -59,6 +71,11 @@ pub struct ShardQueuer {
     ///
     /// This is used to determine how long to wait between shard IDENTIFYs.
     pub last_start: Option<Instant>,
+    /// The number of shard starts that failed in a row, such as by timing out
+    /// while connecting.
+    ///
+    /// The wait between shard starts doubles with each of these.
+    pub failed_starts: u32,
     /// A copy of the sender channel to communicate with the
     /// [`ShardManagerMonitor`].
     ///
@@ -139,8 +156,8 @@ impl ShardQueuer {
         };
 
         // We must wait 5 seconds between IDENTIFYs to avoid session
-        // invalidations.
-        let duration = Duration::from_secs(WAIT_BETWEEN_BOOTS_IN_SECONDS);
+        // invalidations, and back off further if the last starts failed.
+        let duration = start_delay(self.failed_starts);
         let elapsed = instant.elapsed();
 
         if elapsed >= duration {
@@ -162,28 +179,87 @@ impl ShardQueuer {
             info!("[Shard Queuer] Re-queueing start of shard {}", id);
 
             self.queue.push_back((id, total));
+            self.failed_starts = self.failed_starts.saturating_add(1);
+        } else {
+            self.failed_starts = 0;
         }
 
         self.last_start = Some(Instant::now());
     }
 
-    #[instrument(skip(self))]
-    async fn start(&mut self, shard_id: u64, shard_total: u64) -> Result<()> {
-        let shard_info = [shard_id, shard_total];
+    async fn client_build_number(&self) -> u64 {
+        if !self.settings.gateway_config.refresh_client_build_number {
+            return self.settings.gateway_config.client_build_number;
+        }
+
+        let http = &self.cache_and_http.http;
+        match http.fetch_client_build_number_cached(CLIENT_BUILD_NUMBER_MAX_AGE).await {
+            Ok(client_build_number) => client_build_number,
+            Err(why) => {
+                warn!("[Shard Queuer] Failed to fetch the client build number: {:?}", why);
+
+                self.settings.gateway_config.client_build_number
+            },
+        }
+    }
+
+    /// Creates a shard resuming the persisted session of the given shard, if
+    /// any. The session is only tried once: if it can not be resumed, the
+    /// shard identifies instead.
+    async fn resume(&mut self, shard_id: u64, shard_total: u64) -> Option<Shard> {
+        let state = self.settings.resume_sessions.remove(&shard_id)?;
+
+        info!("[Shard Queuer] Resuming the session of shard {}", shard_id);
 
-        let mut shard = Shard::new(
+        let result = Shard::with_session(
             Arc::clone(&self.ws_url),
             &self.cache_and_http.http.token,
-            shard_info
+            [shard_id, shard_total],
+            self.settings.gateway_config.clone(),
+            state,
         )
-        .await?;
+        .await;
+
+        match result {
+            Ok(shard) => Some(shard),
+            Err(why) => {
+                warn!(
+                    "[Shard Queuer] Failed to resume the session of shard {}, identifying: {:?}",
+                    shard_id, why
+                );
+
+                None
+            },
+        }
+    }
+
+    #[instrument(skip(self))]
+    async fn start(&mut self, shard_id: u64, shard_total: u64) -> Result<()> {
+        let shard_info = [shard_id, shard_total];
+
+        let mut shard = match self.resume(shard_id, shard_total).await {
+            Some(shard) => shard,
+            None => {
+                Shard::with_config(
+                    Arc::clone(&self.ws_url),
+                    &self.cache_and_http.http.token,
+                    shard_info,
+                    self.settings.gateway_config.clone(),
+                )
+                .await?
+            },
+        };
 
         shard.set_http(Arc::clone(&self.cache_and_http.http));
+        shard.set_client_build_number(self.client_build_number().await);
 
         let mut runner = ShardRunner::new(ShardRunnerOptions {
             data: Arc::clone(&self.data),
             event_handler: self.event_handler.as_ref().map(Arc::clone),
             raw_event_handler: self.raw_event_handler.as_ref().map(Arc::clone),
+            settings: ShardRunnerSettings {
+                presence_filter: self.settings.presence_filter.as_ref().map(Arc::clone),
+            },
             #[cfg(feature = "framework")]
             framework: Arc::clone(&self.framework),
             manager_tx: self.manager_tx.clone(),
@@ -195,8 +271,13 @@ impl ShardQueuer {
 
         let runner_info = ShardRunnerInfo {
             latency: None,
+            heartbeat_interval: None,
+            heartbeat_failures: 0,
+            command_ratelimiter: runner.shard.command_ratelimiter().clone(),
             runner_tx: ShardMessenger::new(runner.runner_tx()),
             stage: ConnectionStage::Disconnected,
+            session_state: runner.shard.session_state(),
+            status: runner.shard.current_presence().1,
         };
 
         spawn_named("shard_queuer::stop", async move {
@@ -253,3 +334,192 @@ impl ShardQueuer {
         }
     }
 }
+
+/// The time to wait between shard starts, doubling with each consecutive
+/// failed start up to [`MAX_WAIT_AFTER_FAILED_STARTS`].
+fn start_delay(failed_starts: u32) -> Duration {
+    let base = Duration::from_secs(WAIT_BETWEEN_BOOTS_IN_SECONDS);
+
+    base.checked_mul(1 << failed_starts.min(16))
+        .map_or(MAX_WAIT_AFTER_FAILED_STARTS, |delay| delay.min(MAX_WAIT_AFTER_FAILED_STARTS))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, VecDeque};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    use async_tungstenite::tungstenite::Message as WsMessage;
+    use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+    use futures::{SinkExt, StreamExt};
+    use tokio::sync::{Mutex, RwLock};
+    use tokio::time::{timeout, Duration};
+    use typemap_rev::TypeMap;
+
+    use super::{start_delay, ShardQueuer, MAX_WAIT_AFTER_FAILED_STARTS};
+    #[cfg(feature = "framework")]
+    use crate::client::bridge::gateway::NoFramework;
+    use crate::client::bridge::gateway::{ShardId, ShardManagerMessage, ShardManagerSettings};
+    use crate::gateway::{ConnectionStage, GatewayConfig, SessionState};
+    use crate::http::Http;
+    use crate::json::{self, json};
+    use crate::CacheAndHttp;
+
+    fn queuer(
+        url: String,
+        gateway_config: GatewayConfig,
+        resume_sessions: HashMap<u64, SessionState>,
+    ) -> (ShardQueuer, UnboundedReceiver<ShardManagerMessage>) {
+        let (manager_tx, manager_rx) = unbounded();
+        let (_queuer_tx, rx) = unbounded();
+        let queuer = ShardQueuer {
+            data: Arc::new(RwLock::new(TypeMap::new())),
+            event_handler: None,
+            raw_event_handler: None,
+            settings: ShardManagerSettings {
+                presence_filter: None,
+                gateway_config,
+                resume_sessions,
+            },
+            #[cfg(feature = "framework")]
+            framework: Arc::new(NoFramework),
+            last_start: None,
+            failed_starts: 0,
+            manager_tx,
+            queue: VecDeque::new(),
+            runners: Arc::default(),
+            rx,
+            #[cfg(feature = "voice")]
+            voice_manager: None,
+            ws_url: Arc::new(Mutex::new(url)),
+            cache_and_http: Arc::new(CacheAndHttp {
+                #[cfg(feature = "cache")]
+                cache: Arc::default(),
+                http: Arc::new(Http::new("token")),
+            }),
+        };
+
+        (queuer, manager_rx)
+    }
+
+    #[test]
+    fn test_start_delay() {
+        assert_eq!(start_delay(0), Duration::from_secs(5));
+        assert_eq!(start_delay(1), Duration::from_secs(10));
+        assert_eq!(start_delay(3), Duration::from_secs(40));
+        assert_eq!(start_delay(5), MAX_WAIT_AFTER_FAILED_STARTS);
+        assert_eq!(start_delay(u32::MAX), MAX_WAIT_AFTER_FAILED_STARTS);
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_requeues_shard() {
+        // Accepts TCP connections, but never completes the WebSocket
+        // handshake.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let config = GatewayConfig::default().connect_timeout(Duration::from_millis(100));
+        let (mut queuer, _manager_rx) = queuer(url, config, HashMap::new());
+
+        queuer.checked_start(0, 1).await;
+
+        // The shard is queued to be started again, after a longer wait.
+        assert_eq!(queuer.queue, [(0, 1)]);
+        assert_eq!(queuer.failed_starts, 1);
+        assert!(queuer.last_start.is_some());
+        assert!(queuer.runners.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resume_session() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            // The shard resumes over the connection it is created with.
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = async_tungstenite::tokio::accept_async(stream).await.unwrap();
+
+            let resume = ws.next().await.unwrap().unwrap().into_text().unwrap();
+            let resumed = json!({"op": 0, "s": 43, "t": "RESUMED", "d": {"_trace": []}});
+            ws.send(WsMessage::Text(json::to_string(&resumed).unwrap())).await.unwrap();
+
+            (resume, listener, ws)
+        });
+
+        let mut resume_sessions = HashMap::new();
+        resume_sessions.insert(0, SessionState::new("session", 42));
+        let (mut queuer, mut manager_rx) = queuer(url, GatewayConfig::default(), resume_sessions);
+
+        queuer.checked_start(0, 1).await;
+        assert!(queuer.queue.is_empty());
+        assert!(queuer.settings.resume_sessions.is_empty());
+
+        let (mut resume, listener, _ws) = server.await.unwrap();
+        // No other connection was opened.
+        assert!(timeout(Duration::from_millis(100), listener.accept()).await.is_err());
+        let resume: json::Value = json::from_str(resume.as_mut_str()).unwrap();
+        assert_eq!(
+            resume,
+            json!({"op": 6, "d": {"session_id": "session", "seq": 42, "token": "token"}})
+        );
+
+        // The runner reports the session, with the latest sequence number,
+        // once resumed.
+        let session_state = loop {
+            let message = timeout(Duration::from_secs(5), manager_rx.next()).await.unwrap();
+
+            if let Some(ShardManagerMessage::ShardUpdate {
+                id: ShardId(0),
+                stage: ConnectionStage::Connected,
+                session_state,
+                ..
+            }) = message
+            {
+                break session_state;
+            }
+        };
+        assert_eq!(session_state, Some(SessionState::new("session", 43)));
+
+        let runners = queuer.runners.lock().await;
+        assert_eq!(runners[&ShardId(0)].session_state, Some(SessionState::new("session", 42)));
+    }
+
+    #[tokio::test]
+    async fn test_resume_session_failure_identifies() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = async_tungstenite::tokio::accept_async(stream).await.unwrap();
+
+            // Nothing is sent until the Hello, after which the shard
+            // identifies.
+            let hello = json!({"op": 10, "d": {"heartbeat_interval": 45000}});
+            ws.send(WsMessage::Text(json::to_string(&hello).unwrap())).await.unwrap();
+
+            let mut payload = ws.next().await.unwrap().unwrap().into_text().unwrap();
+            let payload: json::Value = json::from_str(payload.as_mut_str()).unwrap();
+
+            (payload, ws)
+        });
+
+        // Nothing listens on the resume URL.
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut state = SessionState::new("session", 42);
+        state.resume_gateway_url = Some(format!("ws://{}", closed.local_addr().unwrap()));
+        drop(closed);
+
+        let mut resume_sessions = HashMap::new();
+        resume_sessions.insert(0, state);
+        let (mut queuer, _manager_rx) = queuer(url, GatewayConfig::default(), resume_sessions);
+
+        queuer.checked_start(0, 1).await;
+        assert!(queuer.queue.is_empty());
+        assert!(queuer.settings.resume_sessions.is_empty());
+        assert!(queuer.runners.lock().await[&ShardId(0)].session_state.is_none());
+
+        let (payload, _ws) = timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+        assert_eq!(payload["op"], 2);
+    }
+}
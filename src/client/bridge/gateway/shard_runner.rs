@@ -1,7 +1,8 @@
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
+use std::time::Duration;
 
-use async_tungstenite::tungstenite;
 use async_tungstenite::tungstenite::error::Error as TungsteniteError;
 use async_tungstenite::tungstenite::protocol::frame::CloseFrame;
 use futures::channel::mpsc::{self, UnboundedReceiver as Receiver, UnboundedSender as Sender};
@@ -11,7 +12,7 @@ use tokio::sync::RwLock;
 use tracing::{debug, error, info, instrument, trace, warn};
 use typemap_rev::TypeMap;
 
-use super::event::{ClientEvent, ShardStageUpdateEvent};
+use super::event::{ClientEvent, ShardReconnectEvent, ShardStageUpdateEvent};
 use super::{ShardClientMessage, ShardId, ShardManagerMessage, ShardRunnerMessage};
 #[cfg(feature = "voice")]
 use crate::client::bridge::voice::VoiceGatewayManager;
@@ -29,19 +30,35 @@ use crate::collector::{
 };
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
-use crate::gateway::{GatewayError, InterMessage, ReconnectType, Shard, ShardAction};
+use crate::gateway::{
+    ConnectionStage,
+    GatewayError,
+    InterMessage,
+    ReconnectType,
+    Shard,
+    ShardAction,
+};
 use crate::internal::prelude::*;
 use crate::internal::ws_impl::{ReceiverExt, SenderExt};
 #[cfg(feature = "collector")]
 use crate::model::application::interaction::Interaction;
-use crate::model::event::{Event, GatewayEvent};
+use crate::model::event::{Event, GatewayEvent, GuildMembersChunkEvent};
+use crate::model::gateway::Presence;
+use crate::model::id::UserId;
 use crate::CacheAndHttp;
 
+/// The delay before resuming after the first heartbeat that could not be
+/// sent, doubled for each consecutive failure.
+const HEARTBEAT_RESUME_BASE_DELAY: Duration = Duration::from_secs(1);
+/// The maximum delay before resuming after a heartbeat could not be sent.
+const HEARTBEAT_RESUME_MAX_DELAY: Duration = Duration::from_secs(60);
+
 /// A runner for managing a [`Shard`] and its respective WebSocket client.
 pub struct ShardRunner {
     data: Arc<RwLock<TypeMap>>,
     event_handler: Option<Arc<dyn EventHandler>>,
     raw_event_handler: Option<Arc<dyn RawEventHandler>>,
+    presence_filter: Option<Arc<HashSet<UserId>>>,
     #[cfg(feature = "framework")]
     framework: Arc<dyn Framework + Send + Sync>,
     manager_tx: Sender<ShardManagerMessage>,
@@ -53,6 +70,15 @@ pub struct ShardRunner {
     #[cfg(feature = "voice")]
     voice_manager: Option<Arc<dyn VoiceGatewayManager + Send + Sync + 'static>>,
     cache_and_http: Arc<CacheAndHttp>,
+    // presences collected so far for pending presence requests, by nonce
+    presence_requests: HashMap<String, (Vec<Presence>, Sender<Vec<Presence>>)>,
+    // close code of the last received event, if it was a close frame
+    last_close_code: Option<u16>,
+    // consecutive resumes due to heartbeats that could not be sent, reset
+    // once connected
+    heartbeat_resumes: u32,
+    /// Commands held back while the shard's command budget is exhausted.
+    queued_commands: VecDeque<InterMessage>,
     #[cfg(feature = "collector")]
     event_filters: Vec<EventFilter>,
     #[cfg(feature = "collector")]
@@ -76,6 +102,7 @@ impl ShardRunner {
             data: opt.data,
             event_handler: opt.event_handler,
             raw_event_handler: opt.raw_event_handler,
+            presence_filter: opt.settings.presence_filter,
             #[cfg(feature = "framework")]
             framework: opt.framework,
             manager_tx: opt.manager_tx,
@@ -83,6 +110,10 @@ impl ShardRunner {
             #[cfg(feature = "voice")]
             voice_manager: opt.voice_manager,
             cache_and_http: opt.cache_and_http,
+            presence_requests: HashMap::new(),
+            last_close_code: None,
+            heartbeat_resumes: 0,
+            queued_commands: VecDeque::new(),
             #[cfg(feature = "collector")]
             event_filters: Vec::new(),
             #[cfg(feature = "collector")]
@@ -130,9 +161,20 @@ impl ShardRunner {
             }
 
             // check heartbeat
+            let heartbeat_failures = self.shard.heartbeat_failures();
             if !self.shard.check_heartbeat().await {
                 warn!("[ShardRunner {:?}] Error heartbeating", self.shard.shard_info(),);
 
+                if self.shard.heartbeat_failures() > heartbeat_failures {
+                    self.update_manager();
+
+                    match self.resume_after_heartbeat_failure().await {
+                        Ok(true) => continue,
+                        Ok(false) => return Ok(()),
+                        Err(_) => {},
+                    }
+                }
+
                 return self.request_restart().await;
             }
 
@@ -141,6 +183,10 @@ impl ShardRunner {
             let post = self.shard.stage();
 
             if post != pre {
+                if post == ConnectionStage::Connected {
+                    self.heartbeat_resumes = 0;
+                }
+
                 self.update_manager();
 
                 let e = ClientEvent::ShardStageUpdate(ShardStageUpdateEvent {
@@ -167,7 +213,7 @@ impl ShardRunner {
                         match self.shard.reconnection_type() {
                             ReconnectType::Reidentify => return self.request_restart().await,
                             ReconnectType::Resume => {
-                                if let Err(why) = self.shard.resume().await {
+                                if let Err(why) = self.resume().await {
                                     warn!(
                                         "[ShardRunner {:?}] Resume failed, reidentifying: {:?}",
                                         self.shard.shard_info(),
@@ -183,7 +229,15 @@ impl ShardRunner {
                 None => {},
             }
 
-            if let Some(event) = event {
+            if let Some(mut event) = event {
+                if !self.filter_presences(&mut event) {
+                    continue;
+                }
+
+                if let Event::GuildMembersChunk(ref chunk) = event {
+                    self.handle_presence_request(chunk);
+                }
+
                 #[cfg(feature = "collector")]
                 {
                     self.handle_filters(&event);
@@ -199,6 +253,57 @@ impl ShardRunner {
         }
     }
 
+    /// Drops the presences of users not in the presence filter, if any,
+    /// returning whether the event should still be dispatched.
+    fn filter_presences(&self, event: &mut Event) -> bool {
+        match (&self.presence_filter, event) {
+            (Some(filter), Event::PresenceUpdate(event)) => {
+                filter.contains(&event.presence.user.id)
+            },
+            (Some(filter), Event::PresencesReplace(event)) => {
+                event.presences.retain(|p| filter.contains(&p.user.id));
+
+                true
+            },
+            _ => true,
+        }
+    }
+
+    /// Collects the presences of a member chunk requested through
+    /// [`ShardRunnerMessage::RequestPresences`], sending them once the last
+    /// chunk has been received.
+    fn handle_presence_request(&mut self, chunk: &GuildMembersChunkEvent) {
+        let nonce = match &chunk.nonce {
+            Some(nonce) if self.presence_requests.contains_key(nonce) => nonce,
+            _ => return,
+        };
+
+        if let Some((presences, _)) = self.presence_requests.get_mut(nonce) {
+            presences.extend(chunk.presences.iter().flatten().cloned());
+        }
+
+        if chunk.chunk_index + 1 >= chunk.chunk_count {
+            if let Some((presences, sender)) = self.presence_requests.remove(nonce) {
+                drop(sender.unbounded_send(presences));
+            }
+        }
+    }
+
+    /// Fails the pending presence requests, as their remaining chunks may
+    /// never be received once the shard reconnects.
+    fn fail_presence_requests(&mut self) {
+        if !self.presence_requests.is_empty() {
+            debug!(
+                "[ShardRunner {:?}] Dropping {} pending presence requests",
+                self.shard.shard_info(),
+                self.presence_requests.len()
+            );
+
+            // Dropping the senders makes the requests fail.
+            self.presence_requests.clear();
+        }
+    }
+
     /// Lets filters check the `event` to send them to collectors if the `event`
     /// is accepted by them.
     #[cfg(feature = "collector")]
@@ -261,7 +366,7 @@ impl ShardRunner {
     async fn action(&mut self, action: &ShardAction) -> Result<()> {
         match *action {
             ShardAction::Reconnect(ReconnectType::Reidentify) => self.request_restart().await,
-            ShardAction::Reconnect(ReconnectType::Resume) => self.shard.resume().await,
+            ShardAction::Reconnect(ReconnectType::Resume) => self.resume().await,
             ShardAction::Heartbeat => self.shard.heartbeat().await,
             ShardAction::Identify => self.shard.identify().await,
         }
@@ -284,31 +389,15 @@ impl ShardRunner {
             return true;
         }
 
-        // Send a Close Frame to Discord, which allows a bot to "log off"
-        drop(
-            self.shard
-                .client
-                .close(Some(CloseFrame {
-                    code: close_code.into(),
-                    reason: Cow::from(""),
-                }))
-                .await,
-        );
-
-        // In return, we wait for either a Close Frame response, or an error, after which this WS is deemed
-        // disconnected from Discord.
-        loop {
-            match self.shard.client.next().await {
-                Some(Ok(tungstenite::Message::Close(_))) => break,
-                Some(Err(_)) => {
-                    warn!(
-                        "[ShardRunner {:?}] Received an error awaiting close frame",
-                        self.shard.shard_info(),
-                    );
-                    break;
-                },
-                _ => continue,
-            }
+        // Send a Close Frame to Discord, which allows a bot to "log off", and
+        // wait for the response, after which this WS is deemed disconnected
+        // from Discord.
+        if let Err(why) = self.shard.shutdown(Some(close_code)).await {
+            warn!(
+                "[ShardRunner {:?}] Error closing the connection: {:?}",
+                self.shard.shard_info(),
+                why
+            );
         }
 
         // Inform the manager that shutdown for this shard has finished.
@@ -335,6 +424,7 @@ impl ShardRunner {
             &self.raw_event_handler,
             &self.runner_tx,
             self.shard.shard_info()[0],
+            self.shard.config().presence_events,
             Arc::clone(&self.cache_and_http),
         )
         .await;
@@ -400,8 +490,24 @@ impl ShardRunner {
                     self.shard.client.close(Some(close)).await.is_ok()
                 },
                 ShardClientMessage::Runner(ShardRunnerMessage::Message(msg)) => {
+                    self.shard.command_ratelimiter().acquire().await;
                     self.shard.client.send(msg).await.is_ok()
                 },
+                ShardClientMessage::Runner(ShardRunnerMessage::RequestPresences {
+                    guild_id,
+                    nonce,
+                    sender,
+                }) => {
+                    let sent = self.shard.request_presences(guild_id, &nonce).await.is_ok();
+
+                    if sent {
+                        // Forget requests which timed out.
+                        self.presence_requests.retain(|_, (_, sender)| !sender.is_closed());
+                        self.presence_requests.insert(nonce, (Vec::new(), sender));
+                    }
+
+                    sent
+                },
                 ShardClientMessage::Runner(ShardRunnerMessage::SetActivity(activity)) => {
                     // To avoid a clone of `activity`, we do a little bit of
                     // trickery here:
@@ -418,17 +524,17 @@ impl ShardRunner {
                     // - the original activity we received over the channel
                     self.shard.set_activity(activity);
 
-                    self.shard.update_presence().await.is_ok()
+                    self.update_presence().await
                 },
                 ShardClientMessage::Runner(ShardRunnerMessage::SetPresence(status, activity)) => {
                     self.shard.set_presence(status, activity);
 
-                    self.shard.update_presence().await.is_ok()
+                    self.update_presence().await
                 },
                 ShardClientMessage::Runner(ShardRunnerMessage::SetStatus(status)) => {
                     self.shard.set_status(status);
 
-                    self.shard.update_presence().await.is_ok()
+                    self.update_presence().await
                 },
                 #[cfg(feature = "collector")]
                 ShardClientMessage::Runner(ShardRunnerMessage::SetEventFilter(collector)) => {
@@ -467,6 +573,7 @@ impl ShardRunner {
             },
             InterMessage::Json(value) => {
                 // Value must be forwarded over the websocket
+                self.shard.command_ratelimiter().acquire().await;
                 self.shard.client.send_json(&value).await.is_ok()
             },
         }
@@ -508,9 +615,36 @@ impl ShardRunner {
     // Returns whether the shard runner is in a state that can continue.
     #[instrument(skip(self))]
     async fn recv(&mut self) -> Result<bool> {
+        // Send the commands held back, as far as the budget allows.
+        while self.shard.command_ratelimiter().remaining() > 0 {
+            match self.queued_commands.pop_front() {
+                Some(value) => {
+                    if !self.handle_rx_value(value).await {
+                        return Ok(false);
+                    }
+                },
+                None => break,
+            }
+        }
+
         loop {
             match self.runner_rx.try_next() {
                 Ok(Some(value)) => {
+                    // Queue commands instead of waiting for the budget, which
+                    // would hold up heartbeating and receiving events.
+                    if is_gateway_command(&value)
+                        && (!self.queued_commands.is_empty()
+                            || self.shard.command_ratelimiter().remaining() == 0)
+                    {
+                        debug!(
+                            "[ShardRunner {:?}] Command budget exhausted; queueing command",
+                            self.shard.shard_info(),
+                        );
+
+                        self.queued_commands.push_back(value);
+                        continue;
+                    }
+
                     if !self.handle_rx_value(value).await {
                         return Ok(false);
                     }
@@ -533,20 +667,45 @@ impl ShardRunner {
         Ok(true)
     }
 
+    /// Sends the shard's current presence, returning whether the connection
+    /// can still be used.
+    ///
+    /// An invalid activity is only logged, as nothing was sent for it.
+    async fn update_presence(&mut self) -> bool {
+        // Report the new status, such as for the auto-away.
+        self.update_manager();
+
+        match self.shard.update_presence().await {
+            Err(Error::Gateway(GatewayError::InvalidActivity(why))) => {
+                warn!(
+                    "[ShardRunner {:?}] Not updating the presence: {}",
+                    self.shard.shard_info(),
+                    why,
+                );
+
+                true
+            },
+            result => result.is_ok(),
+        }
+    }
+
     /// Returns a received event, as well as whether reading the potentially
     /// present event was successful.
     #[instrument(skip(self))]
     async fn recv_event(&mut self) -> Result<(Option<Event>, Option<ShardAction>, bool)> {
-        let gw_event = match self.shard.client.recv_json().await {
+        let max_size = self.shard.max_frame_size();
+        let gw_event = match self.shard.client.recv_json(max_size).await {
             Ok(Some(value)) => GatewayEvent::deserialize(value).map(Some).map_err(From::from),
             Ok(None) => Ok(None),
             Err(Error::Tungstenite(TungsteniteError::Io(_))) => {
                 debug!("Attempting to auto-reconnect");
 
+                self.last_close_code = None;
+
                 match self.shard.reconnection_type() {
                     ReconnectType::Reidentify => return Ok((None, None, false)),
                     ReconnectType::Resume => {
-                        if let Err(why) = self.shard.resume().await {
+                        if let Err(why) = self.resume().await {
                             warn!("Failed to resume: {:?}", why);
 
                             return Ok((None, None, false));
@@ -556,6 +715,29 @@ impl ShardRunner {
 
                 return Ok((None, None, true));
             },
+            Err(Error::Gateway(GatewayError::DecompressionFailed))
+                if self.shard.compress() && self.shard.stage().is_connecting() =>
+            {
+                if let Err(why) = self.shard.reconnect_without_compression().await {
+                    warn!("Failed to reconnect without compression: {:?}", why);
+
+                    return Ok((None, None, false));
+                }
+
+                return Ok((None, None, true));
+            },
+            Err(Error::Gateway(GatewayError::PayloadTooLarge)) => {
+                warn!(
+                    "[ShardRunner {:?}] Received a payload larger than {} bytes; restarting",
+                    self.shard.shard_info(),
+                    max_size,
+                );
+
+                // Resuming would replay the same payload.
+                let action = ShardAction::Reconnect(ReconnectType::Reidentify);
+
+                return Ok((None, Some(action), true));
+            },
             Err(why) => Err(why),
         };
 
@@ -565,6 +747,14 @@ impl ShardRunner {
             Err(why) => Err(why),
         };
 
+        self.last_close_code = match &event {
+            Err(Error::Gateway(GatewayError::Closed(Some(frame)))) => Some(frame.code.into()),
+            _ => None,
+        };
+
+        // Dispatches received before RESUMED are replays of missed events.
+        let resume_replay = self.shard.stage() == ConnectionStage::Resuming;
+
         let action = match self.shard.handle_event(&event) {
             Ok(Some(action)) => Some(action),
             Ok(None) => None,
@@ -626,7 +816,13 @@ impl ShardRunner {
         }
 
         let event = match event {
-            Ok(GatewayEvent::Dispatch(_, event)) => Some(event),
+            Ok(GatewayEvent::Dispatch(_, mut event)) => {
+                if let Event::PresenceUpdate(ref mut event) = event {
+                    event.is_resume_replay = resume_replay;
+                }
+
+                Some(event)
+            },
             _ => None,
         };
 
@@ -635,7 +831,9 @@ impl ShardRunner {
 
     #[instrument(skip(self))]
     async fn request_restart(&mut self) -> Result<()> {
+        self.fail_presence_requests();
         self.update_manager();
+        self.dispatch_reconnect(ReconnectType::Reidentify).await;
 
         debug!("[ShardRunner {:?}] Requesting restart", self.shard.shard_info(),);
         let shard_id = ShardId(self.shard.shard_info()[0]);
@@ -653,21 +851,119 @@ impl ShardRunner {
         Ok(())
     }
 
+    // Resumes the shard's session, informing the event handler.
+    async fn resume(&mut self) -> Result<()> {
+        self.fail_presence_requests();
+        self.dispatch_reconnect(ReconnectType::Resume).await;
+
+        self.shard.resume().await
+    }
+
+    // Resumes the shard's session after a heartbeat could not be sent, which
+    // means the connection was lost. Consecutive attempts are delayed
+    // exponentially.
+    //
+    // Messages received while waiting are still handled, except for commands,
+    // which are queued until the session is resumed. Returns whether the
+    // runner should keep running, or an error if the session can not be
+    // resumed, in which case the shard should be restarted.
+    async fn resume_after_heartbeat_failure(&mut self) -> Result<bool> {
+        if self.shard.reconnection_type() != ReconnectType::Resume {
+            return Err(Error::Gateway(GatewayError::NoSessionId));
+        }
+
+        let delay = (HEARTBEAT_RESUME_BASE_DELAY * (1 << self.heartbeat_resumes.min(6)))
+            .min(HEARTBEAT_RESUME_MAX_DELAY);
+        self.heartbeat_resumes += 1;
+
+        debug!(
+            "[ShardRunner {:?}] Resuming in {:?} after a heartbeat could not be sent",
+            self.shard.shard_info(),
+            delay
+        );
+
+        let deadline = tokio::time::Instant::now() + delay;
+
+        loop {
+            let value = match tokio::time::timeout_at(deadline, self.runner_rx.next()).await {
+                Ok(value) => value,
+                Err(_) => break,
+            };
+
+            match value {
+                Some(value) if is_gateway_command(&value) => self.queued_commands.push_back(value),
+                Some(value) => {
+                    if !self.handle_rx_value(value).await {
+                        return Ok(false);
+                    }
+                },
+                // The runner holds a sender itself, so this can not happen.
+                None => break,
+            }
+        }
+
+        self.resume().await.map(|()| true).map_err(|why| {
+            warn!(
+                "[ShardRunner {:?}] Resume failed, reidentifying: {:?}",
+                self.shard.shard_info(),
+                why
+            );
+
+            why
+        })
+    }
+
+    async fn dispatch_reconnect(&self, kind: ReconnectType) {
+        let e = ClientEvent::ShardReconnect(ShardReconnectEvent {
+            kind,
+            close_code: self.last_close_code,
+            shard_id: ShardId(self.shard.shard_info()[0]),
+        });
+
+        self.dispatch(DispatchEvent::Client(e)).await;
+    }
+
     #[instrument(skip(self))]
     fn update_manager(&self) {
         drop(self.manager_tx.unbounded_send(ShardManagerMessage::ShardUpdate {
             id: ShardId(self.shard.shard_info()[0]),
             latency: self.shard.latency(),
+            heartbeat_interval: self.shard.heartbeat_interval(),
+            heartbeat_failures: self.shard.heartbeat_failures(),
             stage: self.shard.stage(),
+            session_state: self.shard.session_state(),
+            status: self.shard.current_presence().1,
         }));
     }
 }
 
+/// Whether a message is sent over the gateway as a command, counting towards
+/// the shard's [`CommandRatelimiter`].
+///
+/// [`CommandRatelimiter`]: crate::gateway::CommandRatelimiter
+fn is_gateway_command(value: &InterMessage) -> bool {
+    match value {
+        InterMessage::Client(value) => matches!(
+            **value,
+            ShardClientMessage::Runner(
+                ShardRunnerMessage::ChunkGuild { .. }
+                    | ShardRunnerMessage::Message(_)
+                    | ShardRunnerMessage::RequestPresences { .. }
+                    | ShardRunnerMessage::SetActivity(_)
+                    | ShardRunnerMessage::SetPresence(..)
+                    | ShardRunnerMessage::SetStatus(_)
+            )
+        ),
+        InterMessage::Json(_) => true,
+    }
+}
+
 /// Options to be passed to [`ShardRunner::new`].
 pub struct ShardRunnerOptions {
     pub data: Arc<RwLock<TypeMap>>,
     pub event_handler: Option<Arc<dyn EventHandler>>,
     pub raw_event_handler: Option<Arc<dyn RawEventHandler>>,
+    pub settings: ShardRunnerSettings,
     #[cfg(feature = "framework")]
     pub framework: Arc<dyn Framework + Send + Sync>,
     pub manager_tx: Sender<ShardManagerMessage>,
@@ -676,3 +972,146 @@ pub struct ShardRunnerOptions {
     pub voice_manager: Option<Arc<dyn VoiceGatewayManager + Send + Sync>>,
     pub cache_and_http: Arc<CacheAndHttp>,
 }
+
+/// Optional settings of a [`ShardRunner`].
+///
+/// New settings may be added without it being a breaking change, so this is
+/// made with [`Default::default`], and the fields set afterwards.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct ShardRunnerSettings {
+    /// The users whose presence updates are dispatched, if filtered.
+    pub presence_filter: Option<Arc<HashSet<UserId>>>,
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use futures::channel::mpsc::unbounded;
+    use futures::StreamExt;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+    use tokio::sync::{Mutex, RwLock};
+    use tokio::time::{timeout, Duration};
+    use typemap_rev::TypeMap;
+
+    use super::{ShardRunner, ShardRunnerOptions, ShardRunnerSettings};
+    #[cfg(feature = "framework")]
+    use crate::client::bridge::gateway::NoFramework;
+    use crate::client::bridge::gateway::{
+        ShardClientMessage,
+        ShardId,
+        ShardManagerMessage,
+        ShardMessenger,
+    };
+    use crate::gateway::{InterMessage, SessionState, Shard};
+    use crate::http::Http;
+    use crate::json::{self, json, Value};
+    use crate::model::user::OnlineStatus;
+    use crate::CacheAndHttp;
+
+    /// Accepts gateway connections, forwarding the payloads sent over them.
+    async fn gateway() -> (String, UnboundedReceiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (tx, rx) = unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let tx = tx.clone();
+
+                tokio::spawn(async move {
+                    let mut ws = async_tungstenite::tokio::accept_async(stream).await.unwrap();
+
+                    while let Some(Ok(message)) = ws.next().await {
+                        if let Ok(mut text) = message.into_text() {
+                            if let Ok(value) = json::from_str(text.as_mut_str()) {
+                                drop(tx.send(value));
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        (url, rx)
+    }
+
+    /// Creates a runner for a shard which resumed a session, and lost its
+    /// connection.
+    async fn runner(url: String, received: &mut UnboundedReceiver<Value>) -> ShardRunner {
+        let mut shard = Shard::new(Arc::new(Mutex::new(url)), "token", [0, 1]).await.unwrap();
+        shard.resume_from(SessionState::new("session", 1)).await.unwrap();
+        assert_eq!(received.recv().await.unwrap()["op"], 6);
+
+        shard.client.close(None).await.unwrap();
+
+        let (manager_tx, _manager_rx) = unbounded();
+        ShardRunner::new(ShardRunnerOptions {
+            data: Arc::new(RwLock::new(TypeMap::new())),
+            event_handler: None,
+            raw_event_handler: None,
+            settings: ShardRunnerSettings::default(),
+            #[cfg(feature = "framework")]
+            framework: Arc::new(NoFramework),
+            manager_tx,
+            shard,
+            #[cfg(feature = "voice")]
+            voice_manager: None,
+            cache_and_http: Arc::new(CacheAndHttp {
+                #[cfg(feature = "cache")]
+                cache: Arc::default(),
+                http: Arc::new(Http::new("token")),
+            }),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_heartbeat_failure() {
+        let (url, mut received) = gateway().await;
+        let mut runner = runner(url, &mut received).await;
+
+        // Commands sent while waiting to resume are held back.
+        ShardMessenger::new(runner.runner_tx()).set_status(OnlineStatus::Idle);
+
+        assert!(runner.resume_after_heartbeat_failure().await.unwrap());
+        assert_eq!(runner.heartbeat_resumes, 1);
+        assert_eq!(runner.queued_commands.len(), 1);
+
+        let resume = timeout(Duration::from_secs(5), received.recv()).await.unwrap().unwrap();
+        assert_eq!(
+            resume,
+            json!({"op": 6, "d": {"session_id": "session", "seq": 1, "token": "token"}})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_while_waiting_to_resume() {
+        let (url, mut received) = gateway().await;
+        let mut runner = runner(url, &mut received).await;
+
+        // The next resume would be a minute away.
+        runner.heartbeat_resumes = 6;
+
+        let shutdown = ShardManagerMessage::Shutdown(ShardId(0), 1000);
+        let message = InterMessage::Client(Box::new(ShardClientMessage::Manager(shutdown)));
+        runner.runner_tx().unbounded_send(message).unwrap();
+
+        let resume = timeout(Duration::from_secs(5), runner.resume_after_heartbeat_failure());
+        assert!(!resume.await.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_presence_requests_fail_on_reconnect() {
+        let (url, mut received) = gateway().await;
+        let mut runner = runner(url, &mut received).await;
+
+        let (requests_tx, mut requests_rx) = unbounded();
+        runner.presence_requests.insert("nonce".to_string(), (Vec::new(), requests_tx));
+
+        assert!(runner.resume_after_heartbeat_failure().await.unwrap());
+        assert!(runner.presence_requests.is_empty());
+        assert!(timeout(Duration::from_secs(5), requests_rx.next()).await.unwrap().is_none());
+    }
+}
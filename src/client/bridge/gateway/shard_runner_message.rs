@@ -1,4 +1,5 @@
 use async_tungstenite::tungstenite::Message;
+use futures::channel::mpsc::UnboundedSender as Sender;
 
 #[cfg(feature = "collector")]
 use crate::collector::{
@@ -8,7 +9,7 @@ use crate::collector::{
     ModalInteractionFilter,
     ReactionFilter,
 };
-use crate::model::gateway::Activity;
+use crate::model::gateway::{Activity, Presence};
 use crate::model::id::{GuildId, UserId};
 use crate::model::user::OnlineStatus;
 
@@ -40,8 +41,10 @@ pub enum ShardRunnerMessage {
         limit: Option<u16>,
         /// A filter to apply to the returned members.
         filter: ChunkGuildFilter,
-        /// Optional nonce to identify [`GuildMembersChunkEvent`] responses.
+        /// Optional nonce to identify [`GuildMembersChunkEvent`] responses, at
+        /// most [`MAX_NONCE_LENGTH`] characters long.
         ///
+        /// [`MAX_NONCE_LENGTH`]: crate::gateway::MAX_NONCE_LENGTH
         /// [`GuildMembersChunkEvent`]: crate::model::event::GuildMembersChunkEvent
         nonce: Option<String>,
     },
@@ -56,6 +59,20 @@ pub enum ShardRunnerMessage {
     Close(u16, Option<String>),
     /// Indicates that the client is to send a custom WebSocket message.
     Message(Message),
+    /// Indicates that the client is to request the presences of all of a
+    /// guild's members, sending them through `sender` once every chunk has
+    /// been received.
+    RequestPresences {
+        /// The Id of the guild to request the presences of.
+        guild_id: GuildId,
+        /// The nonce identifying the [`GuildMembersChunkEvent`]s of this
+        /// request.
+        ///
+        /// [`GuildMembersChunkEvent`]: crate::model::event::GuildMembersChunkEvent
+        nonce: String,
+        /// The channel to send the collected presences through.
+        sender: Sender<Vec<Presence>>,
+    },
     /// Indicates that the client is to update the shard's presence's activity.
     SetActivity(Option<Activity>),
     /// Indicates that the client is to update the shard's presence in its
@@ -1,6 +1,14 @@
 use std::sync::Arc;
+#[cfg(feature = "gateway")]
+use std::time::Duration;
+#[cfg(feature = "cache")]
+use std::time::Instant;
 
+#[cfg(feature = "gateway")]
+use futures::channel::mpsc;
 use futures::channel::mpsc::UnboundedSender as Sender;
+#[cfg(feature = "gateway")]
+use futures::StreamExt;
 use tokio::sync::RwLock;
 use typemap_rev::TypeMap;
 
@@ -14,6 +22,7 @@ use crate::collector::{ComponentInteractionFilter, MessageFilter, ReactionFilter
 use crate::gateway::InterMessage;
 use crate::http::Http;
 use crate::model::prelude::*;
+use crate::Result;
 
 /// The context is a general utility struct provided on event dispatches, which
 /// helps with dealing with the current "context" of the event dispatch.
@@ -248,6 +257,9 @@ impl Context {
     /// "Resets" the current user's presence, by setting the activity to [`None`]
     /// and the online status to [`Online`].
     ///
+    /// This sends an empty activity list, which also clears a custom status
+    /// that was set previously.
+    ///
     /// Use [`Self::set_presence`] for fine-grained control over individual details.
     ///
     /// # Examples
@@ -424,6 +436,75 @@ impl Context {
     pub async fn set_component_interaction_filter(&self, filter: ComponentInteractionFilter) {
         self.shard.set_component_interaction_filter(filter);
     }
+
+    /// Fetches the bot gateway information, including the recommended number
+    /// of shards and the [`SessionStartLimit`].
+    ///
+    /// This is a shortcut for [`Http::get_bot_gateway`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Http`] if the request failed.
+    ///
+    /// [`Error::Http`]: crate::Error::Http
+    #[inline]
+    pub async fn fetch_gateway_info(&self) -> Result<BotGateway> {
+        self.http.get_bot_gateway().await
+    }
+
+    /// Retrieves a clone of a user's presence in a guild from the cache.
+    ///
+    /// This is a shortcut for [`Cache::guild_presence`].
+    #[cfg(feature = "cache")]
+    #[inline]
+    #[must_use]
+    pub fn get_presence(&self, guild_id: GuildId, user_id: UserId) -> Option<Presence> {
+        self.cache.guild_presence(guild_id, user_id)
+    }
+
+    /// Retrieves when a change of a user's status was last received.
+    ///
+    /// This is a shortcut for [`Cache::user_status_since`].
+    #[cfg(feature = "cache")]
+    #[inline]
+    #[must_use]
+    pub fn user_status_since(&self, user_id: UserId) -> Option<Instant> {
+        self.cache.user_status_since(user_id)
+    }
+
+    /// Fetches the presences of all of a guild's members over the gateway.
+    ///
+    /// This requests the guild's members with their presences, and waits
+    /// until every [`GuildMembersChunkEvent`] of the request has been
+    /// received, for at most `timeout`. The chunks are still dispatched to the
+    /// event handler as usual.
+    ///
+    /// **Note**: Requesting presences requires the `GUILD_PRESENCES` intent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Other`] if the shard stopped handling the request,
+    /// such as when it could not be sent or the shard reconnected, or if not
+    /// every chunk was received within `timeout`.
+    ///
+    /// [`Error::Other`]: crate::Error::Other
+    #[cfg(feature = "gateway")]
+    pub async fn fetch_all_presences(
+        &self,
+        guild_id: GuildId,
+        timeout: Duration,
+    ) -> Result<Vec<Presence>> {
+        let nonce = crate::gateway::generate_nonce();
+        let (sender, mut receiver) = mpsc::unbounded();
+
+        self.shard.request_presences(guild_id, nonce, sender);
+
+        match tokio::time::timeout(timeout, receiver.next()).await {
+            Ok(Some(presences)) => Ok(presences),
+            Ok(None) => Err(crate::Error::Other("The presence request was dropped")),
+            Err(_) => Err(crate::Error::Other("Timed out waiting for the presence request")),
+        }
+    }
 }
 
 impl AsRef<Http> for Context {
@@ -21,7 +21,7 @@ use crate::gateway::InterMessage;
 use crate::http::Http;
 use crate::internal::tokio::spawn_named;
 use crate::model::channel::{Channel, Message};
-use crate::model::event::Event;
+use crate::model::event::{Event, PresenceEvent};
 use crate::model::guild::Member;
 #[cfg(feature = "cache")]
 use crate::model::id::GuildId;
@@ -167,6 +167,7 @@ pub(crate) fn dispatch<'rec>(
     raw_event_handler: &'rec Option<Arc<dyn RawEventHandler>>,
     runner_tx: &'rec Sender<InterMessage>,
     shard_id: u64,
+    presence_events: bool,
     cache_and_http: Arc<CacheAndHttp>,
 ) -> BoxFuture<'rec, ()> {
     async move {
@@ -227,7 +228,16 @@ pub(crate) fn dispatch<'rec>(
                     }
                 },
                 other => {
-                    handle_event(other, data, h, runner_tx, shard_id, cache_and_http).await;
+                    handle_event(
+                        other,
+                        data,
+                        h,
+                        runner_tx,
+                        shard_id,
+                        presence_events,
+                        cache_and_http,
+                    )
+                    .await;
                 },
             },
             (None, Some(ref rh)) => {
@@ -305,8 +315,16 @@ pub(crate) fn dispatch<'rec>(
                         }
                     },
                     other => {
-                        handle_event(other, data, handler, runner_tx, shard_id, cache_and_http)
-                            .await;
+                        handle_event(
+                            other,
+                            data,
+                            handler,
+                            runner_tx,
+                            shard_id,
+                            presence_events,
+                            cache_and_http,
+                        )
+                        .await;
                     },
                 }
             },
@@ -341,6 +359,7 @@ async fn handle_event(
     event_handler: &Arc<dyn EventHandler>,
     runner_tx: &Sender<InterMessage>,
     shard_id: u64,
+    presence_events: bool,
     cache_and_http: Arc<CacheAndHttp>,
 ) {
     #[cfg(not(feature = "cache"))]
@@ -360,12 +379,22 @@ async fn handle_event(
                         event_handler.shard_stage_update(context, event).await;
                     });
                 },
+                ClientEvent::ShardReconnect(event) => {
+                    spawn_named("dispatch::event_handler::shard_reconnect", async move {
+                        event_handler.shard_reconnect(context, event).await;
+                    });
+                },
             }
         },
     };
 
     // Handle Event, this is done to prevent indenting twice (once to destructure DispatchEvent, then to destructure Event)
     match model_event {
+        Event::ActivityJoinRequest(event) => {
+            spawn_named("dispatch::event_handler::activity_join_request", async move {
+                event_handler.activity_join_request(context, event).await;
+            });
+        },
         Event::ApplicationCommandPermissionsUpdate(event) => {
             spawn_named(
                 "dispatch::event_handler::application_command_permissions_update",
@@ -663,10 +692,24 @@ async fn handle_event(
             });
         },
         Event::PresenceUpdate(mut event) => {
-            update(&cache_and_http, &mut event);
+            let _before = update(&cache_and_http, &mut event);
+
+            let presence_events = if presence_events {
+                feature_cache! {{
+                    PresenceEvent::from_update(_before.as_ref(), &event)
+                } else {
+                    PresenceEvent::from_update(None, &event)
+                }}
+            } else {
+                Vec::new()
+            };
 
             spawn_named("dispatch::event_handler::presence_update", async move {
-                event_handler.presence_update(context, event.presence).await;
+                event_handler.presence_update(context.clone(), event.presence).await;
+
+                for presence_event in presence_events {
+                    event_handler.presence_event(context.clone(), presence_event).await;
+                }
             });
         },
         Event::ReactionAdd(event) => {
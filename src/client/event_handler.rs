@@ -14,6 +14,12 @@ use crate::model::prelude::*;
 /// The core trait for handling events by serenity.
 #[async_trait]
 pub trait EventHandler: Send + Sync {
+    /// Dispatched when a user asks to join the current user's activity party.
+    ///
+    /// Provides the request's data. The request can be accepted with
+    /// [`ActivityJoinRequestEvent::accept`].
+    async fn activity_join_request(&self, _ctx: Context, _request: ActivityJoinRequestEvent) {}
+
     /// Dispatched when the permissions of an application command was updated.
     ///
     /// Provides said permission's data.
@@ -385,7 +391,12 @@ pub trait EventHandler: Send + Sync {
     ) {
     }
 
-    /// This event is legacy, and likely no longer sent by discord.
+    /// Dispatched when the presences of the current user's friends are
+    /// replaced in bulk.
+    ///
+    /// Provides the new presences, which have already been stored in the
+    /// cache. Discord sends this to user accounts at certain times, such as
+    /// shortly after connecting, while bot accounts likely never receive it.
     async fn presence_replace(&self, _ctx: Context, _: Vec<Presence>) {}
 
     /// Dispatched when a user's presence is updated (e.g off -> on).
@@ -394,8 +405,33 @@ pub trait EventHandler: Send + Sync {
     ///
     /// Note: This event will not trigger unless the "guild presences" privileged intent
     /// is enabled on the bot application page.
+    ///
+    /// Presence updates replayed by the gateway while resuming a session are
+    /// dispatched too, as they are changes missed while disconnected. They
+    /// can be told apart in [`Self::presence_event`] or [`RawEventHandler`] by
+    /// checking [`PresenceUpdateEvent::is_resume_replay`].
+    ///
+    /// [`RawEventHandler`]: crate::client::RawEventHandler
     async fn presence_update(&self, _ctx: Context, _new_data: Presence) {}
 
+    /// Dispatched for each change resulting from a user's presence update,
+    /// after [`Self::presence_update`].
+    ///
+    /// This allows handling presence updates, user data changes and activity
+    /// changes in one place. Refer to [`PresenceEvent::from_update`] for when
+    /// each event is dispatched; enabling the `cache` feature makes them more
+    /// accurate.
+    ///
+    /// Like [`Self::presence_update`], this is also dispatched for presence
+    /// updates replayed while resuming a session, which are marked in the
+    /// [`PresenceEvent::Updated`] event.
+    ///
+    /// This is only dispatched when enabled with
+    /// [`GatewayConfig::presence_events`].
+    ///
+    /// [`GatewayConfig::presence_events`]: crate::gateway::GatewayConfig::presence_events
+    async fn presence_event(&self, _ctx: Context, _event: PresenceEvent) {}
+
     /// Dispatched upon startup.
     ///
     /// Provides data about the bot and the guilds it's in.
@@ -409,6 +445,13 @@ pub trait EventHandler: Send + Sync {
     /// Provides the context of the shard and the event information about the update.
     async fn shard_stage_update(&self, _ctx: Context, _: ShardStageUpdateEvent) {}
 
+    /// Dispatched when a shard starts reconnecting, either by resuming its
+    /// session or by starting a new one.
+    ///
+    /// Provides whether the session is resumed, and the close code that
+    /// caused the reconnect, if any.
+    async fn shard_reconnect(&self, _ctx: Context, _: ShardReconnectEvent) {}
+
     /// Dispatched when a user starts typing.
     async fn typing_start(&self, _ctx: Context, _: TypingStartEvent) {}
 
@@ -18,6 +18,8 @@
 
 pub mod bridge;
 
+#[cfg(feature = "gateway")]
+mod auto_idle;
 mod context;
 #[cfg(feature = "gateway")]
 mod dispatch;
@@ -25,6 +27,8 @@ mod error;
 #[cfg(feature = "gateway")]
 mod event_handler;
 
+#[cfg(feature = "gateway")]
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -35,12 +39,15 @@ use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, error, info, instrument};
 use typemap_rev::{TypeMap, TypeMapKey};
 
+#[cfg(feature = "gateway")]
+pub use self::auto_idle::AutoIdle;
 #[cfg(feature = "gateway")]
 use self::bridge::gateway::{
     ShardManager,
     ShardManagerError,
     ShardManagerMonitor,
     ShardManagerOptions,
+    ShardManagerSettings,
 };
 #[cfg(feature = "voice")]
 use self::bridge::voice::VoiceGatewayManager;
@@ -49,7 +56,7 @@ pub use self::error::Error as ClientError;
 #[cfg(feature = "gateway")]
 pub use self::event_handler::{EventHandler, RawEventHandler};
 #[cfg(feature = "gateway")]
-use super::gateway::GatewayError;
+use super::gateway::{GatewayConfig, GatewayError, SessionState};
 #[cfg(feature = "cache")]
 pub use crate::cache::Cache;
 #[cfg(feature = "cache")]
@@ -58,8 +65,9 @@ use crate::cache::Settings as CacheSettings;
 use crate::framework::Framework;
 use crate::http::Http;
 use crate::internal::prelude::*;
+use crate::internal::tokio::spawn_named;
 #[cfg(feature = "gateway")]
-use crate::model::id::ApplicationId;
+use crate::model::id::{ApplicationId, UserId};
 pub use crate::CacheAndHttp;
 
 /// A builder implementing [`Future`] building a [`Client`] to interact with Discord.
@@ -79,6 +87,10 @@ pub struct ClientBuilder {
     voice_manager: Option<Arc<dyn VoiceGatewayManager + Send + Sync + 'static>>,
     event_handler: Option<Arc<dyn EventHandler>>,
     raw_event_handler: Option<Arc<dyn RawEventHandler>>,
+    auto_idle: Option<AutoIdle>,
+    presence_filter: Option<Arc<HashSet<UserId>>>,
+    gateway_config: GatewayConfig,
+    resume_sessions: HashMap<u64, SessionState>,
 }
 
 #[cfg(feature = "gateway")]
@@ -96,6 +108,10 @@ impl ClientBuilder {
             voice_manager: None,
             event_handler: None,
             raw_event_handler: None,
+            auto_idle: None,
+            presence_filter: None,
+            gateway_config: GatewayConfig::default(),
+            resume_sessions: HashMap::new(),
         }
     }
 
@@ -308,6 +324,74 @@ impl ClientBuilder {
     pub fn get_raw_event_handler(&self) -> Option<Arc<dyn RawEventHandler>> {
         self.raw_event_handler.clone()
     }
+
+    /// Sets the current user's status automatically after a period of
+    /// inactivity. Refer to [`AutoIdle`] for more information.
+    pub fn auto_idle(mut self, auto_idle: AutoIdle) -> Self {
+        self.auto_idle = Some(auto_idle);
+
+        self
+    }
+
+    /// Gets the auto-away, if set. See [`Self::auto_idle`] for more info.
+    pub fn get_auto_idle(&self) -> Option<&AutoIdle> {
+        self.auto_idle.as_ref()
+    }
+
+    /// Only receive presence updates of the given users.
+    ///
+    /// Presence updates of any other user are dropped by the shard runners
+    /// right after being received, before they reach the cache or the event
+    /// handlers. This is useful for user accounts, which receive the presence
+    /// updates of every user they share a guild with.
+    pub fn presence_filter(mut self, user_ids: impl IntoIterator<Item = UserId>) -> Self {
+        self.presence_filter = Some(Arc::new(user_ids.into_iter().collect()));
+
+        self
+    }
+
+    /// Gets the users whose presence updates are received, if filtered. See
+    /// [`Self::presence_filter`] for more info.
+    pub fn get_presence_filter(&self) -> Option<&HashSet<UserId>> {
+        self.presence_filter.as_deref()
+    }
+
+    /// Sets the settings of the shards' connections to the gateway.
+    ///
+    /// Refer to [`GatewayConfig`] for the available settings and their
+    /// defaults.
+    pub fn gateway_config(mut self, gateway_config: GatewayConfig) -> Self {
+        self.gateway_config = gateway_config;
+
+        self
+    }
+
+    /// Gets the settings of the shards' connections to the gateway. See
+    /// [`Self::gateway_config`] for more info.
+    pub fn get_gateway_config(&self) -> &GatewayConfig {
+        &self.gateway_config
+    }
+
+    /// Sets a previously persisted session for the shard with the given Id to
+    /// resume when it is first started, instead of identifying.
+    ///
+    /// The state of each shard's session can be retrieved with
+    /// [`ShardManager::session_states`], e.g. to persist it before the process
+    /// exits. Refer to [`SessionState`] for how long a session can be resumed.
+    ///
+    /// If the session can not be resumed, such as when its resume URL can not
+    /// be connected to, the shard identifies instead.
+    pub fn resume_session(mut self, shard_id: u64, state: SessionState) -> Self {
+        self.resume_sessions.insert(shard_id, state);
+
+        self
+    }
+
+    /// Gets the sessions to resume, by shard Id. See [`Self::resume_session`]
+    /// for more info.
+    pub fn get_resume_sessions(&self) -> &HashMap<u64, SessionState> {
+        &self.resume_sessions
+    }
 }
 
 #[cfg(feature = "gateway")]
@@ -325,6 +409,10 @@ impl Future for ClientBuilder {
                 If you don't want to use the command framework, disable default features and specify all features you want to use.");
             let event_handler = self.event_handler.take();
             let raw_event_handler = self.raw_event_handler.take();
+            let auto_idle = self.auto_idle.take();
+            let presence_filter = self.presence_filter.take();
+            let gateway_config = self.gateway_config.clone();
+            let resume_sessions = std::mem::take(&mut self.resume_sessions);
 
             let mut http = self.http.take().unwrap();
             if let Some(event_handler) = event_handler.clone() {
@@ -358,6 +446,11 @@ impl Future for ClientBuilder {
                         data: &data,
                         event_handler: &event_handler,
                         raw_event_handler: &raw_event_handler,
+                        settings: ShardManagerSettings {
+                            presence_filter,
+                            gateway_config,
+                            resume_sessions,
+                        },
                         #[cfg(feature = "framework")]
                         framework: &framework,
                         shard_index: 0,
@@ -379,6 +472,7 @@ impl Future for ClientBuilder {
                     voice_manager,
                     ws_url,
                     cache_and_http,
+                    auto_idle,
                 })
             }));
         }
@@ -625,6 +719,10 @@ pub struct Client {
     pub ws_url: Arc<Mutex<String>>,
     /// A container for an optional cache and HTTP client.
     pub cache_and_http: Arc<CacheAndHttp>,
+    /// The auto-away managed by the client, if any.
+    ///
+    /// Refer to [`AutoIdle`] for more information.
+    pub auto_idle: Option<AutoIdle>,
 }
 
 impl Client {
@@ -937,7 +1035,20 @@ impl Client {
             }
         }
 
-        if let Err(why) = self.shard_manager_worker.run().await {
+        let mut auto_idle = None;
+        if let Some(config) = self.auto_idle.clone() {
+            let runners = Arc::clone(&self.shard_manager.lock().await.runners);
+
+            auto_idle = Some(spawn_named("client::auto_idle", config.run(runners)));
+        }
+
+        let result = self.shard_manager_worker.run().await;
+
+        if let Some(auto_idle) = auto_idle {
+            auto_idle.abort();
+        }
+
+        if let Err(why) = result {
             let err = match why {
                 ShardManagerError::DisallowedGatewayIntents => {
                     GatewayError::DisallowedGatewayIntents
@@ -1,5 +1,7 @@
 //! A set of constants used by the library.
 
+use std::convert::TryFrom;
+
 /// The maximum length of the textual size of an embed.
 pub const EMBED_MAX_LENGTH: usize = 6000;
 
@@ -9,10 +11,54 @@ pub const EMBED_MAX_COUNT: usize = 10;
 /// The maximum number of stickers in a message.
 pub const STICKER_MAX_COUNT: usize = 3;
 
+/// The maximum number of buttons in an activity.
+pub const ACTIVITY_BUTTON_MAX_COUNT: usize = 2;
+
+/// The maximum unicode code points allowed within the label of an activity
+/// button.
+pub const ACTIVITY_BUTTON_LABEL_MAX_LENGTH: usize = 32;
+
+/// The maximum unicode code points allowed within the hover texts of activity
+/// assets.
+pub const ACTIVITY_ASSET_TEXT_MAX_LENGTH: usize = 128;
+
 /// The gateway version used by the library. The gateway URL is retrieved via
 /// the REST API.
 pub const GATEWAY_VERSION: u8 = 10;
 
+/// The maximum number of commands which can be sent over a gateway connection
+/// within a minute, after which Discord closes the connection.
+pub const GATEWAY_COMMAND_LIMIT: usize = 120;
+
+/// The client build number sent on identify by default.
+///
+/// Discord regularly releases new builds of its client, and may treat
+/// sessions identifying with an old build differently. This is the build
+/// that was current when this version of the library was released; a recent
+/// one can be fetched with [`Http::fetch_client_build_number`].
+///
+/// [`Http::fetch_client_build_number`]: crate::http::Http::fetch_client_build_number
+pub const CLIENT_BUILD_NUMBER: u64 = 193906;
+
+/// The page of the web client whose scripts are searched for the client
+/// build number.
+pub const CLIENT_APP_URL: &str = "https://discord.com/login";
+
+/// The capabilities sent on identify by default.
+///
+/// Capabilities are a bitfield of client features which change the shape of
+/// some payloads, such as deduplicating user objects in READY (`1 << 4`) or
+/// sending guilds in a different format (`1 << 10`). The library only parses
+/// the payloads in their original shape, and the value sent by the web client
+/// enables such features, so none are enabled by default.
+///
+/// Capabilities can be set with [`GatewayConfig::capabilities`], such as to
+/// receive data missing from READY, as long as the payloads the library
+/// parses keep their shape. Otherwise, deserializing them fails.
+///
+/// [`GatewayConfig::capabilities`]: crate::gateway::GatewayConfig::capabilities
+pub const IDENTIFY_CAPABILITIES: u64 = 0;
+
 /// The large threshold to send on identify.
 pub const LARGE_THRESHOLD: u8 = 250;
 
@@ -25,7 +71,8 @@ pub const MEMBER_FETCH_LIMIT: u64 = 1000;
 /// The [UserAgent] sent along with every request.
 ///
 /// [UserAgent]: ::reqwest::header::USER_AGENT
-pub const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:109.0) Gecko/20100101 Firefox/113.0";
+pub const USER_AGENT: &str =
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:109.0) Gecko/20100101 Firefox/113.0";
 
 /// List of messages Discord shows on member join.
 pub static JOIN_MESSAGES: &[&str] = &[
@@ -74,6 +121,9 @@ pub enum OpCode {
     Hello = 10,
     /// Sent immediately following a client heartbeat that was received.
     HeartbeatAck = 11,
+    /// Used by user accounts to request a sync of guilds' members and
+    /// presences.
+    GuildSync = 12,
     /// Unknown opcode.
     Unknown = !0,
 }
@@ -91,8 +141,45 @@ enum_number!(OpCode {
     InvalidSession,
     Hello,
     HeartbeatAck,
+    GuildSync,
 });
 
+impl TryFrom<u8> for OpCode {
+    /// The opcode that is not known to the library.
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::Event,
+            1 => Self::Heartbeat,
+            2 => Self::Identify,
+            3 => Self::StatusUpdate,
+            4 => Self::VoiceStateUpdate,
+            5 => Self::VoiceServerPing,
+            6 => Self::Resume,
+            7 => Self::Reconnect,
+            8 => Self::GetGuildMembers,
+            9 => Self::InvalidSession,
+            10 => Self::Hello,
+            11 => Self::HeartbeatAck,
+            12 => Self::GuildSync,
+            _ => return Err(value),
+        })
+    }
+}
+
+impl From<OpCode> for u8 {
+    /// Converts the opcode into its numeric value.
+    ///
+    /// [`OpCode::Unknown`] converts to [`u8::MAX`].
+    fn from(op: OpCode) -> Self {
+        match op {
+            OpCode::Unknown => u8::MAX,
+            op => op as u8,
+        }
+    }
+}
+
 pub mod close_codes {
     /// Unknown error; try reconnecting?
     ///
@@ -143,3 +230,22 @@ pub mod close_codes {
     /// Disallowed gateway intents have been provided.
     pub const DISALLOWED_GATEWAY_INTENTS: u16 = 4014;
 }
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::OpCode;
+
+    #[test]
+    fn test_opcode_u8_round_trip() {
+        for value in 0..=12 {
+            let op = OpCode::try_from(value).unwrap();
+            assert_eq!(u8::from(op), value);
+            assert_eq!(op.num(), u64::from(value));
+        }
+
+        assert_eq!(OpCode::try_from(13), Err(13));
+        assert_eq!(u8::from(OpCode::Unknown), u8::MAX);
+    }
+}
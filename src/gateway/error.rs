@@ -3,6 +3,8 @@ use std::fmt;
 
 use async_tungstenite::tungstenite::protocol::CloseFrame;
 
+use crate::model::gateway::ActivityError;
+
 /// An error that occurred while attempting to deal with the gateway.
 ///
 /// Note that - from a user standpoint - there should be no situation in which
@@ -35,6 +37,19 @@ pub enum Error {
     NoAuthentication,
     /// When a session Id was expected (for resuming), but was not present.
     NoSessionId,
+    /// When the activity of a presence update would be rejected by the
+    /// gateway, such as because it has too many buttons.
+    InvalidActivity(ActivityError),
+    /// When a compressed payload received from the gateway could not be
+    /// decompressed.
+    DecompressionFailed,
+    /// When a payload received from the gateway decompressed to more than
+    /// [`GatewayConfig::max_frame_size`] bytes.
+    ///
+    /// [`GatewayConfig::max_frame_size`]: super::GatewayConfig::max_frame_size
+    PayloadTooLarge,
+    /// When the WebSocket connection to the gateway did not open in time.
+    ConnectTimeout,
     /// When a shard would have too many guilds assigned to it.
     ///
     /// # Examples
@@ -68,6 +83,13 @@ impl fmt::Display for Error {
             Self::InvalidShardData => f.write_str("Sent invalid shard data"),
             Self::NoAuthentication => f.write_str("Sent no authentication"),
             Self::NoSessionId => f.write_str("No Session Id present when required"),
+            Self::InvalidActivity(why) => {
+                f.write_str("Invalid activity: ")?;
+                fmt::Display::fmt(why, f)
+            },
+            Self::DecompressionFailed => f.write_str("Failed to decompress a payload"),
+            Self::PayloadTooLarge => f.write_str("Decompressed payload exceeded the maximum size"),
+            Self::ConnectTimeout => f.write_str("Timed out connecting to the gateway"),
             Self::OverloadedShard => f.write_str("Shard has too many guilds"),
             Self::ReconnectFailure => f.write_str("Failed to Reconnect"),
             Self::InvalidGatewayIntents => f.write_str("Invalid gateway intents were provided"),
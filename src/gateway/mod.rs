@@ -47,14 +47,20 @@
 //! [docs]: https://discordapp.com/developers/docs/topics/gateway#sharding
 
 mod error;
+mod ratelimiter;
 mod shard;
 mod ws_client_ext;
 
 use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
 
 pub use self::error::Error as GatewayError;
+pub use self::ratelimiter::CommandRatelimiter;
 pub use self::shard::Shard;
-pub use self::ws_client_ext::WebSocketGatewayClientExt;
+pub use self::ws_client_ext::{presence_update_payload, WebSocketGatewayClientExt};
 #[cfg(feature = "client")]
 use crate::client::bridge::gateway::ShardClientMessage;
 use crate::json::Value;
@@ -68,6 +74,200 @@ use async_tungstenite::WebSocketStream;
 
 pub type WsStream = WebSocketStream<ConnectStream>;
 
+/// The maximum length of a nonce sent in a gateway request.
+pub const MAX_NONCE_LENGTH: usize = 25;
+
+/// The default maximum size, in bytes, of a message received from the
+/// gateway.
+///
+/// This is far above the size of any legitimate payload, and only guards
+/// against allocating memory for malformed frames.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// The default time to wait for the WebSocket connection to the gateway to
+/// open before giving up, after which the connection is attempted again.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default time to wait for the gateway's Hello after connecting before
+/// reconnecting.
+pub const DEFAULT_HELLO_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The settings of the connections made by shards to the gateway.
+///
+/// This is given to [`Shard::with_config`], or to the [`ClientBuilder`] to
+/// apply it to every shard of a [`Client`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use serenity::gateway::GatewayConfig;
+///
+/// let config = GatewayConfig::default()
+///     .connect_timeout(Duration::from_secs(10))
+///     .hello_timeout(Duration::from_secs(5));
+///
+/// assert_eq!(config.connect_timeout, Duration::from_secs(10));
+/// ```
+///
+/// [`ClientBuilder`]: crate::client::ClientBuilder::gateway_config
+/// [`Client`]: crate::Client
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct GatewayConfig {
+    /// The maximum size, in bytes, of messages received from the gateway.
+    ///
+    /// Larger messages, and compressed ones which decompress to more, make the
+    /// shard reconnect instead of allocating memory for them. This guards
+    /// against malformed frames, such as ones sent by an untrusted proxy.
+    /// Defaults to [`DEFAULT_MAX_FRAME_SIZE`].
+    pub max_frame_size: usize,
+    /// The capabilities sent when identifying.
+    ///
+    /// These tell Discord which client features are supported, and change the
+    /// shape of some payloads, notably READY. Defaults to
+    /// [`IDENTIFY_CAPABILITIES`], which explains what setting them affects.
+    ///
+    /// [`IDENTIFY_CAPABILITIES`]: crate::constants::IDENTIFY_CAPABILITIES
+    pub capabilities: u64,
+    /// The client build number sent when identifying.
+    ///
+    /// Discord regularly releases new builds of its client, so user accounts
+    /// should identify with a recent one. A warning is logged when shards
+    /// identify with a build older than the default, [`CLIENT_BUILD_NUMBER`].
+    ///
+    /// [`CLIENT_BUILD_NUMBER`]: crate::constants::CLIENT_BUILD_NUMBER
+    pub client_build_number: u64,
+    /// Whether the [`Client`] fetches the current client build number before
+    /// starting a shard, falling back to [`Self::client_build_number`].
+    ///
+    /// The build number is fetched with
+    /// [`Http::fetch_client_build_number_cached`], and reused for an hour.
+    /// This is ignored by stand-alone shards. Defaults to `false`.
+    ///
+    /// [`Http::fetch_client_build_number_cached`]: crate::http::Http::fetch_client_build_number_cached
+    ///
+    /// [`Client`]: crate::Client
+    pub refresh_client_build_number: bool,
+    /// The time to wait for the WebSocket connection to open.
+    ///
+    /// The [`Client`] queues shards that fail to connect in time to be
+    /// started again. The usual 5 second wait between shard starts doubles
+    /// with each failed start in a row, up to 2 minutes. Defaults to
+    /// [`DEFAULT_CONNECT_TIMEOUT`].
+    pub connect_timeout: Duration,
+    /// The time to wait for the gateway's Hello after connecting, after which
+    /// the shard is restarted, like when heartbeats are not acknowledged.
+    ///
+    /// Defaults to [`DEFAULT_HELLO_TIMEOUT`].
+    pub hello_timeout: Duration,
+    /// Whether the [`Client`] dispatches [`EventHandler::presence_event`].
+    ///
+    /// Deriving these events from every presence update has a cost, so they
+    /// are only dispatched when enabled. This is ignored by stand-alone
+    /// shards. Defaults to `false`.
+    ///
+    /// [`Client`]: crate::Client
+    /// [`EventHandler::presence_event`]: crate::client::EventHandler::presence_event
+    pub presence_events: bool,
+}
+
+impl GatewayConfig {
+    /// Sets the maximum size, in bytes, of messages received from the
+    /// gateway.
+    #[must_use]
+    pub fn max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+
+        self
+    }
+
+    /// Sets the capabilities sent when identifying.
+    #[must_use]
+    pub fn capabilities(mut self, capabilities: u64) -> Self {
+        self.capabilities = capabilities;
+
+        self
+    }
+
+    /// Sets the client build number sent when identifying.
+    #[must_use]
+    pub fn client_build_number(mut self, client_build_number: u64) -> Self {
+        self.client_build_number = client_build_number;
+
+        self
+    }
+
+    /// Sets whether the [`Client`] fetches the current client build number
+    /// before starting a shard.
+    ///
+    /// [`Client`]: crate::Client
+    #[must_use]
+    pub fn refresh_client_build_number(mut self, refresh: bool) -> Self {
+        self.refresh_client_build_number = refresh;
+
+        self
+    }
+
+    /// Sets the time to wait for the WebSocket connection to open.
+    #[must_use]
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+
+        self
+    }
+
+    /// Sets the time to wait for the gateway's Hello after connecting.
+    #[must_use]
+    pub fn hello_timeout(mut self, hello_timeout: Duration) -> Self {
+        self.hello_timeout = hello_timeout;
+
+        self
+    }
+
+    /// Sets whether the [`Client`] dispatches
+    /// [`EventHandler::presence_event`].
+    ///
+    /// [`Client`]: crate::Client
+    /// [`EventHandler::presence_event`]: crate::client::EventHandler::presence_event
+    #[must_use]
+    pub fn presence_events(mut self, presence_events: bool) -> Self {
+        self.presence_events = presence_events;
+
+        self
+    }
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            capabilities: crate::constants::IDENTIFY_CAPABILITIES,
+            client_build_number: crate::constants::CLIENT_BUILD_NUMBER,
+            refresh_client_build_number: false,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            hello_timeout: DEFAULT_HELLO_TIMEOUT,
+            presence_events: false,
+        }
+    }
+}
+
+/// Generates a nonce for a gateway request, such as when requesting guild
+/// members.
+///
+/// The nonce is unique within the process, and at most
+/// [`MAX_NONCE_LENGTH`] characters long.
+#[must_use]
+pub fn generate_nonce() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    format!("{:x}-{:x}", millis, count)
+}
+
 /// Indicates the current connection stage of a [`Shard`].
 ///
 /// This can be useful for knowing which shards are currently "down"/"up".
@@ -168,7 +368,7 @@ pub enum ShardAction {
 }
 
 /// The type of reconnection that should be performed.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[non_exhaustive]
 pub enum ReconnectType {
     /// Indicator that a new connection should be made by sending an IDENTIFY.
@@ -176,3 +376,48 @@ pub enum ReconnectType {
     /// Indicator that a new connection should be made by sending a RESUME.
     Resume,
 }
+
+/// The state needed to resume a gateway session, as returned by
+/// [`Shard::session_state`].
+///
+/// This can be serialized, e.g. written to disk before the process exits, and
+/// passed to [`Shard::resume_from`] on the next launch, to resume the session
+/// instead of identifying again. When using a [`Client`], the states of its
+/// shards are available from [`ShardManager::session_states`], and are resumed
+/// with [`ClientBuilder::resume_session`].
+///
+/// **Note**: A session can only be resumed shortly after it was disconnected,
+/// usually within a few minutes. Discord invalidates older sessions, in which
+/// case the shard receives an `INVALID_SESSION` and identifies again. A
+/// session that was closed with the `1000` or `1001` close codes, such as by
+/// [`Shard::shutdown`] without a code, can not be resumed at all.
+///
+/// [`Client`]: crate::Client
+/// [`ClientBuilder::resume_session`]: crate::client::ClientBuilder::resume_session
+/// [`ShardManager::session_states`]: crate::client::bridge::gateway::ShardManager::session_states
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[non_exhaustive]
+pub struct SessionState {
+    /// The Id of the session, as received in the Ready event.
+    pub session_id: String,
+    /// The sequence number of the last event received.
+    pub seq: u64,
+    /// The URL to connect to when resuming, as received in the Ready event.
+    ///
+    /// If not set, the shard's gateway URL is used.
+    #[serde(default)]
+    pub resume_gateway_url: Option<String>,
+}
+
+impl SessionState {
+    /// Creates a new session state, to resume the session with the given Id
+    /// from the given sequence number.
+    #[must_use]
+    pub fn new(session_id: impl Into<String>, seq: u64) -> Self {
+        Self {
+            session_id: session_id.into(),
+            seq,
+            resume_gateway_url: None,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+use crate::constants::GATEWAY_COMMAND_LIMIT;
+
+/// The window in which at most [`GATEWAY_COMMAND_LIMIT`] commands can be
+/// sent.
+const WINDOW: Duration = Duration::from_secs(60);
+/// The number of commands left out of the budget, for the exempt heartbeats,
+/// identifies and resumes.
+const RESERVED: usize = 5;
+
+/// Limits the commands sent over a gateway connection, such as presence
+/// updates, guild member requests and voice state updates.
+///
+/// Discord closes the connection of a client sending more than
+/// [`GATEWAY_COMMAND_LIMIT`] commands in a minute. The limiter keeps a budget
+/// slightly below that, so that heartbeats, identifies and resumes, which are
+/// exempt from it and always sent immediately, can not push the shard over the
+/// limit.
+///
+/// Commands sent while the budget is exhausted are held back until enough
+/// time has passed: the [`ShardRunner`] queues them in order, while the
+/// [`Shard`] methods wait before sending.
+///
+/// Clones share the same budget.
+///
+/// [`ShardRunner`]: crate::client::bridge::gateway::ShardRunner
+/// [`Shard`]: super::Shard
+#[derive(Clone, Debug, Default)]
+pub struct CommandRatelimiter {
+    sent: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+impl CommandRatelimiter {
+    /// The number of commands which can be sent within a minute.
+    pub const BUDGET: usize = GATEWAY_COMMAND_LIMIT - RESERVED;
+
+    /// Retrieves the number of commands which can currently be sent without
+    /// waiting.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        Self::BUDGET - self.prune().len()
+    }
+
+    /// Retrieves how long until a command can be sent, or [`None`] if one can
+    /// be sent right away.
+    #[must_use]
+    pub fn reset_in(&self) -> Option<Duration> {
+        let sent = self.prune();
+
+        if sent.len() < Self::BUDGET {
+            return None;
+        }
+
+        sent.front().map(|oldest| WINDOW.saturating_sub(oldest.elapsed()))
+    }
+
+    /// Takes a command out of the budget if there is one left, returning
+    /// whether it could.
+    pub(crate) fn try_acquire(&self) -> bool {
+        let mut sent = self.prune();
+
+        if sent.len() < Self::BUDGET {
+            sent.push_back(Instant::now());
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Takes a command out of the budget, waiting until one is available.
+    pub(crate) async fn acquire(&self) {
+        while !self.try_acquire() {
+            if let Some(delay) = self.reset_in() {
+                debug!("Gateway command budget exhausted; waiting {:?}", delay);
+
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+
+    /// Locks the instants commands were sent at, dropping those which are no
+    /// longer within the window.
+    fn prune(&self) -> MutexGuard<'_, VecDeque<Instant>> {
+        // The guarded instants can not be left in an invalid state.
+        let mut sent = self.sent.lock().unwrap_or_else(PoisonError::into_inner);
+
+        while matches!(sent.front(), Some(at) if at.elapsed() >= WINDOW) {
+            sent.pop_front();
+        }
+
+        sent
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{CommandRatelimiter, WINDOW};
+
+    #[test]
+    fn test_command_ratelimiter() {
+        let ratelimiter = CommandRatelimiter::default();
+        assert_eq!(ratelimiter.remaining(), CommandRatelimiter::BUDGET);
+        assert_eq!(ratelimiter.reset_in(), None);
+
+        for _ in 0..CommandRatelimiter::BUDGET {
+            assert!(ratelimiter.clone().try_acquire());
+        }
+
+        assert_eq!(ratelimiter.remaining(), 0);
+        assert!(!ratelimiter.try_acquire());
+        assert!(matches!(ratelimiter.reset_in(), Some(delay) if delay <= WINDOW));
+
+        // Commands sent more than a minute ago no longer count.
+        let mut sent = ratelimiter.prune();
+        let expired = sent[0].checked_sub(WINDOW + Duration::from_secs(1)).unwrap();
+        sent[0] = expired;
+        sent[1] = expired;
+        drop(sent);
+
+        assert_eq!(ratelimiter.remaining(), 2);
+        assert!(ratelimiter.try_acquire());
+        assert_eq!(ratelimiter.remaining(), 1);
+    }
+}
@@ -1,17 +1,23 @@
+use std::borrow::Cow;
 use std::sync::Arc;
 use std::time::{Duration as StdDuration, Instant};
 
 use async_tungstenite::tungstenite::error::Error as TungsteniteError;
 use async_tungstenite::tungstenite::protocol::frame::CloseFrame;
+use async_tungstenite::tungstenite::Message;
+use futures::{SinkExt, StreamExt};
 use tokio::sync::Mutex;
+use tokio::time::timeout;
 use tracing::{debug, error, info, instrument, trace, warn};
-use url::Url;
 
 use super::{
+    CommandRatelimiter,
     ConnectionStage,
     CurrentPresence,
+    GatewayConfig,
     GatewayError,
     ReconnectType,
+    SessionState,
     ShardAction,
     WebSocketGatewayClientExt,
     WsStream,
@@ -22,7 +28,7 @@ use crate::http::Http;
 use crate::internal::prelude::*;
 use crate::internal::ws_impl::create_client;
 use crate::model::event::{Event, GatewayEvent};
-use crate::model::gateway::Activity;
+use crate::model::gateway::{Activity, GatewayUrl};
 use crate::model::id::GuildId;
 use crate::model::user::OnlineStatus;
 
@@ -60,6 +66,13 @@ use crate::model::user::OnlineStatus;
 /// [module docs]: crate::gateway#sharding
 pub struct Shard {
     pub client: WsStream,
+    /// The budget of commands which can be sent over the connection.
+    command_ratelimiter: CommandRatelimiter,
+    /// Whether payload compression is requested when identifying.
+    ///
+    /// This is disabled if the gateway sends payloads that can not be
+    /// decompressed.
+    compress: bool,
     current_presence: CurrentPresence,
     /// A tuple of:
     ///
@@ -71,6 +84,8 @@ pub struct Shard {
     /// [`latency`]: fn@Self::latency
     heartbeat_instants: (Option<Instant>, Option<Instant>),
     heartbeat_interval: Option<u64>,
+    /// The number of heartbeats that could not be sent.
+    heartbeat_failures: u64,
     http: Option<Arc<Http>>,
     /// This is used by the heartbeater to determine whether the last
     /// heartbeat was sent without an acknowledgement, and whether to reconnect.
@@ -79,6 +94,9 @@ pub struct Shard {
     last_heartbeat_acknowledged: bool,
     seq: u64,
     session_id: Option<String>,
+    /// The URL to connect to when resuming the session, as received in the
+    /// Ready event.
+    resume_ws_url: Option<String>,
     shard_info: [u64; 2],
     stage: ConnectionStage,
     /// Instant of when the shard was started.
@@ -86,7 +104,8 @@ pub struct Shard {
     // not started within a decent amount of time.
     pub started: Instant,
     pub token: String,
-    ws_url: Arc<Mutex<String>>
+    ws_url: Arc<Mutex<String>>,
+    config: GatewayConfig,
 }
 
 impl Shard {
@@ -129,10 +148,73 @@ impl Shard {
         ws_url: Arc<Mutex<String>>,
         token: &str,
         shard_info: [u64; 2]
+    ) -> Result<Shard> {
+        Self::with_config(ws_url, token, shard_info, GatewayConfig::default()).await
+    }
+
+    /// Instantiates a new instance of a Shard like [`Self::new`], with the
+    /// given connection settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GatewayError::ConnectTimeout`] if the connection did not open
+    /// within [`GatewayConfig::connect_timeout`], and otherwise the same
+    /// errors as [`Self::new`].
+    pub async fn with_config(
+        ws_url: Arc<Mutex<String>>,
+        token: &str,
+        shard_info: [u64; 2],
+        config: GatewayConfig,
     ) -> Result<Shard> {
         let url = ws_url.lock().await.clone();
-        let client = connect(&url).await?;
+        let client = connect(&url, &config).await?;
 
+        Ok(Self::with_client(client, ws_url, token, shard_info, config))
+    }
+
+    /// Instantiates a new instance of a Shard like [`Self::with_config`],
+    /// resuming a previously persisted session instead of identifying.
+    ///
+    /// The connection is made to the session's resume URL, falling back to
+    /// the gateway URL, and the RESUME is sent over it. If Discord no longer
+    /// considers the session valid, the shard receives an `INVALID_SESSION`
+    /// and identifies again as usual.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Self::with_config`], or an error if the
+    /// RESUME could not be sent.
+    pub async fn with_session(
+        ws_url: Arc<Mutex<String>>,
+        token: &str,
+        shard_info: [u64; 2],
+        config: GatewayConfig,
+        state: SessionState,
+    ) -> Result<Shard> {
+        let url = match &state.resume_gateway_url {
+            Some(url) => url.clone(),
+            None => ws_url.lock().await.clone(),
+        };
+        let client = connect(&url, &config).await?;
+
+        let mut shard = Self::with_client(client, ws_url, token, shard_info, config);
+        shard.client.send_resume(&shard_info, &state.session_id, state.seq, token).await?;
+
+        shard.session_id = Some(state.session_id);
+        shard.seq = state.seq;
+        shard.resume_ws_url = state.resume_gateway_url;
+        shard.stage = ConnectionStage::Resuming;
+
+        Ok(shard)
+    }
+
+    fn with_client(
+        client: WsStream,
+        ws_url: Arc<Mutex<String>>,
+        token: &str,
+        shard_info: [u64; 2],
+        config: GatewayConfig,
+    ) -> Shard {
         let current_presence = (None, OnlineStatus::Online);
         let heartbeat_instants = (None, None);
         let heartbeat_interval = None;
@@ -141,11 +223,14 @@ impl Shard {
         let stage = ConnectionStage::Handshake;
         let session_id = None;
 
-        Ok(Shard {
+        Shard {
             client,
+            command_ratelimiter: CommandRatelimiter::default(),
+            compress: true,
             current_presence,
             heartbeat_instants,
             heartbeat_interval,
+            heartbeat_failures: 0,
             http: None,
             last_heartbeat_acknowledged,
             seq,
@@ -153,9 +238,11 @@ impl Shard {
             started: Instant::now(),
             token: token.to_string(),
             session_id,
+            resume_ws_url: None,
             shard_info,
-            ws_url
-        })
+            ws_url,
+            config,
+        }
     }
 
     /// Sets the associated [`Http`] client.
@@ -165,6 +252,93 @@ impl Shard {
         self.http = Some(http);
     }
 
+    /// Retrieves the settings of the shard's connection.
+    #[inline]
+    pub fn config(&self) -> &GatewayConfig {
+        &self.config
+    }
+
+    /// Retrieves the capabilities sent when identifying.
+    #[inline]
+    pub fn capabilities(&self) -> u64 {
+        self.config.capabilities
+    }
+
+    /// Sets the capabilities sent when identifying, which takes effect on the
+    /// next identify.
+    ///
+    /// Refer to [`IDENTIFY_CAPABILITIES`] for more information.
+    ///
+    /// [`IDENTIFY_CAPABILITIES`]: constants::IDENTIFY_CAPABILITIES
+    pub fn set_capabilities(&mut self, capabilities: u64) {
+        self.config.capabilities = capabilities;
+    }
+
+    /// Retrieves the client build number sent when identifying.
+    #[inline]
+    pub fn client_build_number(&self) -> u64 {
+        self.config.client_build_number
+    }
+
+    /// Sets the client build number sent when identifying, which takes
+    /// effect on the next identify.
+    ///
+    /// A warning is logged if the build number is older than
+    /// [`CLIENT_BUILD_NUMBER`], as it is then likely stale.
+    ///
+    /// [`CLIENT_BUILD_NUMBER`]: constants::CLIENT_BUILD_NUMBER
+    pub fn set_client_build_number(&mut self, client_build_number: u64) {
+        if client_build_number < constants::CLIENT_BUILD_NUMBER {
+            warn!(
+                "[Shard {:?}] Client build number {} is older than the default {}, and is likely stale",
+                self.shard_info,
+                client_build_number,
+                constants::CLIENT_BUILD_NUMBER,
+            );
+        }
+
+        self.config.client_build_number = client_build_number;
+    }
+
+    /// Retrieves the maximum size, in bytes, of messages received from the
+    /// gateway.
+    #[inline]
+    pub fn max_frame_size(&self) -> usize {
+        self.config.max_frame_size
+    }
+
+    /// Retrieves the time to wait for the WebSocket connection to open.
+    #[inline]
+    pub fn connect_timeout(&self) -> StdDuration {
+        self.config.connect_timeout
+    }
+
+    /// Sets the time to wait for the WebSocket connection to open when
+    /// reconnecting.
+    ///
+    /// Defaults to [`DEFAULT_CONNECT_TIMEOUT`].
+    ///
+    /// [`DEFAULT_CONNECT_TIMEOUT`]: super::DEFAULT_CONNECT_TIMEOUT
+    pub fn set_connect_timeout(&mut self, connect_timeout: StdDuration) {
+        self.config.connect_timeout = connect_timeout;
+    }
+
+    /// Retrieves the time to wait for the gateway's Hello after connecting.
+    #[inline]
+    pub fn hello_timeout(&self) -> StdDuration {
+        self.config.hello_timeout
+    }
+
+    /// Sets the time to wait for the gateway's Hello after connecting, after
+    /// which [`Self::check_heartbeat`] fails so that the shard reconnects.
+    ///
+    /// Defaults to [`DEFAULT_HELLO_TIMEOUT`].
+    ///
+    /// [`DEFAULT_HELLO_TIMEOUT`]: super::DEFAULT_HELLO_TIMEOUT
+    pub fn set_hello_timeout(&mut self, hello_timeout: StdDuration) {
+        self.config.hello_timeout = hello_timeout;
+    }
+
     /// Retrieves the current presence of the shard.
     #[inline]
     pub fn current_presence(&self) -> &CurrentPresence {
@@ -211,6 +385,8 @@ impl Shard {
                 Ok(())
             },
             Err(why) => {
+                self.heartbeat_failures += 1;
+
                 match why {
                     Error::Tungstenite(TungsteniteError::Io(err)) => {
                         if err.raw_os_error() != Some(32) {
@@ -227,9 +403,29 @@ impl Shard {
         }
     }
 
+    /// Retrieves the heartbeat interval negotiated with the gateway.
+    ///
+    /// This is the interval sent by Discord in the HELLO payload, and is
+    /// `None` until one has been received.
+    #[inline]
+    pub fn heartbeat_interval(&self) -> Option<StdDuration> {
+        self.heartbeat_interval.map(StdDuration::from_millis)
+    }
+
+    /// Retrieves the number of heartbeats that could not be sent, such as
+    /// because the connection was lost, since the shard was created.
     #[inline]
-    pub fn heartbeat_interval(&self) -> Option<&u64> {
-        self.heartbeat_interval.as_ref()
+    pub fn heartbeat_failures(&self) -> u64 {
+        self.heartbeat_failures
+    }
+
+    /// Retrieves the ratelimiter of the commands sent over the connection.
+    ///
+    /// Use [`CommandRatelimiter::remaining`] for the number of commands which
+    /// can currently be sent without waiting.
+    #[inline]
+    pub fn command_ratelimiter(&self) -> &CommandRatelimiter {
+        &self.command_ratelimiter
     }
 
     #[inline]
@@ -247,6 +443,21 @@ impl Shard {
         self.session_id.as_ref()
     }
 
+    /// Returns the state needed to resume the current session, if there is
+    /// one.
+    ///
+    /// This can be persisted, and passed to [`Self::resume_from`] later to
+    /// resume the session, e.g. after restarting the process. Refer to
+    /// [`SessionState`] for how long a session can be resumed.
+    #[must_use]
+    pub fn session_state(&self) -> Option<SessionState> {
+        self.session_id.as_ref().map(|session_id| SessionState {
+            session_id: session_id.clone(),
+            seq: self.seq,
+            resume_gateway_url: self.resume_ws_url.clone(),
+        })
+    }
+
     #[inline]
     #[instrument(skip(self))]
     pub fn set_activity(&mut self, activity: Option<Activity>) {
@@ -315,6 +526,12 @@ impl Shard {
         self.stage
     }
 
+    /// Whether payload compression is requested when identifying.
+    #[inline]
+    pub fn compress(&self) -> bool {
+        self.compress
+    }
+
     #[instrument(skip(self))]
     fn handle_gateway_dispatch(&mut self, seq: u64, event: &Event) -> Option<ShardAction> {
         if seq > self.seq + 1 {
@@ -325,7 +542,17 @@ impl Shard {
             Event::Ready(ref ready) => {
                 debug!("[Shard {:?}] Received Ready", self.shard_info);
 
+                if !ready.ready.is_supported_version() {
+                    warn!(
+                        "[Shard {:?}] Gateway version {} differs from the supported version {}",
+                        self.shard_info,
+                        ready.ready.version,
+                        constants::GATEWAY_VERSION,
+                    );
+                }
+
                 self.session_id = Some(ready.ready.session_id.clone());
+                self.resume_ws_url.clone_from(&ready.ready.resume_gateway_url);
                 self.stage = ConnectionStage::Connected;
 
                 if let Some(ref http) = self.http {
@@ -504,14 +731,14 @@ impl Shard {
             Ok(GatewayEvent::Hello(interval)) => {
                 debug!("[Shard {:?}] Received a Hello; interval: {}", self.shard_info, interval);
 
-                if self.stage == ConnectionStage::Resuming {
-                    return Ok(None);
-                }
-
                 if interval > 0 {
                     self.heartbeat_interval = Some(interval);
                 }
 
+                if self.stage == ConnectionStage::Resuming {
+                    return Ok(None);
+                }
+
                 Ok(Some(if self.stage == ConnectionStage::Handshake {
                     ShardAction::Identify
                 } else {
@@ -531,6 +758,12 @@ impl Shard {
             },
             Ok(GatewayEvent::Reconnect) => Ok(Some(ShardAction::Reconnect(ReconnectType::Resume))),
             Err(Error::Gateway(GatewayError::Closed(ref data))) => self.handle_gateway_closed(data),
+            Err(Error::Tungstenite(TungsteniteError::Capacity(ref why))) => {
+                warn!("[Shard {:?}] Rejected oversized message: {}", self.shard_info, why);
+                info!("[Shard {:?}] Will attempt to auto-reconnect", self.shard_info);
+
+                Ok(Some(ShardAction::Reconnect(self.reconnection_type())))
+            },
             Err(Error::Tungstenite(ref why)) => {
                 warn!("[Shard {:?}] Websocket error: {:?}", self.shard_info, why);
                 info!("[Shard {:?}] Will attempt to auto-reconnect", self.shard_info);
@@ -557,6 +790,7 @@ impl Shard {
     /// `false` is returned under one of the following conditions:
     ///
     /// - a heartbeat acknowledgement was not received in time
+    /// - no Hello was received within [`Self::hello_timeout`] of connecting
     /// - an error occurred while heartbeating
     #[instrument(skip(self))]
     pub async fn check_heartbeat(&mut self) -> bool {
@@ -564,7 +798,7 @@ impl Shard {
             let heartbeat_interval = match self.heartbeat_interval {
                 Some(heartbeat_interval) => heartbeat_interval,
                 None => {
-                    return self.started.elapsed() < StdDuration::from_secs(15);
+                    return self.started.elapsed() < self.config.hello_timeout;
                 },
             };
 
@@ -651,6 +885,11 @@ impl Shard {
     /// If the `cache` feature is enabled, the cache will automatically be
     /// updated with member chunks.
     ///
+    /// The chunks carry the request's `nonce`, which is returned so that they
+    /// can be matched to it. If no nonce is given, one is generated with
+    /// [`generate_nonce`]. A nonce must be at most [`MAX_NONCE_LENGTH`]
+    /// characters long, or Discord ignores it.
+    ///
     /// # Examples
     ///
     /// Chunk a single guild by Id, limiting to 2000 [`Member`]s, and not
@@ -708,6 +947,8 @@ impl Shard {
     /// [`Event::GuildMembersChunk`]: crate::model::event::Event::GuildMembersChunk
     /// [`Guild`]: crate::model::guild::Guild
     /// [`Member`]: crate::model::guild::Member
+    /// [`generate_nonce`]: super::generate_nonce
+    /// [`MAX_NONCE_LENGTH`]: super::MAX_NONCE_LENGTH
     #[instrument(skip(self))]
     pub async fn chunk_guild(
         &mut self,
@@ -715,10 +956,40 @@ impl Shard {
         limit: Option<u16>,
         filter: ChunkGuildFilter,
         nonce: Option<&str>,
-    ) -> Result<()> {
+    ) -> Result<String> {
         debug!("[Shard {:?}] Requesting member chunks", self.shard_info);
 
-        self.client.send_chunk_guild(guild_id, &self.shard_info, limit, filter, nonce).await
+        let nonce = nonce.map_or_else(super::generate_nonce, ToString::to_string);
+
+        self.command_ratelimiter.acquire().await;
+        self.client
+            .send_chunk_guild(guild_id, &self.shard_info, limit, filter, Some(&nonce), false)
+            .await?;
+
+        Ok(nonce)
+    }
+
+    /// Requests the presences of all of a [`Guild`]'s members.
+    ///
+    /// The presences are received through [`Event::GuildMembersChunk`]s
+    /// carrying the given `nonce`, alongside the members themselves.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Tungstenite`] if the payload could not be sent.
+    ///
+    /// [`Event::GuildMembersChunk`]: crate::model::event::Event::GuildMembersChunk
+    /// [`Guild`]: crate::model::guild::Guild
+    #[instrument(skip(self))]
+    pub async fn request_presences(&mut self, guild_id: GuildId, nonce: &str) -> Result<()> {
+        debug!("[Shard {:?}] Requesting member presences", self.shard_info);
+
+        let filter = ChunkGuildFilter::None;
+
+        self.command_ratelimiter.acquire().await;
+        self.client
+            .send_chunk_guild(guild_id, &self.shard_info, None, filter, Some(nonce), true)
+            .await
     }
 
     /// Sets the shard as going into identifying stage, which sets:
@@ -727,7 +998,9 @@ impl Shard {
     /// - the `stage` to [`ConnectionStage::Identifying`]
     #[instrument(skip(self))]
     pub async fn identify(&mut self) -> Result<()> {
-        self.client.send_identify(&self.shard_info, &self.token).await?;
+        self.client
+            .send_identify_with_config(&self.shard_info, &self.token, self.compress, &self.config)
+            .await?;
 
         self.heartbeat_instants.0 = Some(Instant::now());
         self.stage = ConnectionStage::Identifying;
@@ -743,6 +1016,11 @@ impl Shard {
     pub async fn initialize(&mut self) -> Result<WsStream> {
         debug!("[Shard {:?}] Initializing.", self.shard_info);
 
+        let url = self.ws_url.lock().await.clone();
+        self.connect_to(&url).await
+    }
+
+    async fn connect_to(&mut self, url: &str) -> Result<WsStream> {
         // We need to do two, sort of three things here:
         //
         // - set the stage of the shard as opening the websocket connection
@@ -753,8 +1031,7 @@ impl Shard {
         // accurate when a Hello is received.
         self.stage = ConnectionStage::Connecting;
         self.started = Instant::now();
-        let url = &self.ws_url.lock().await.clone();
-        let client = connect(url).await?;
+        let client = connect(url, &self.config).await?;
         self.stage = ConnectionStage::Handshake;
 
         Ok(client)
@@ -766,15 +1043,29 @@ impl Shard {
         self.heartbeat_interval = None;
         self.last_heartbeat_acknowledged = true;
         self.session_id = None;
+        self.resume_ws_url = None;
         self.stage = ConnectionStage::Disconnected;
         self.seq = 0;
     }
 
+    /// Reconnects and resumes the current session.
+    ///
+    /// The connection is made to the resume URL received in the Ready event,
+    /// falling back to the gateway URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GatewayError::NoSessionId`] if there is no session to
+    /// resume, or an error if the new WebSocket connection could not be
+    /// opened.
     #[instrument(skip(self))]
     pub async fn resume(&mut self) -> Result<()> {
         debug!("[Shard {:?}] Attempting to resume", self.shard_info);
 
-        self.client = self.initialize().await?;
+        self.client = match self.resume_ws_url.clone() {
+            Some(url) => self.connect_to(&url).await?,
+            None => self.initialize().await?,
+        };
         self.stage = ConnectionStage::Resuming;
 
         match self.session_id.as_ref() {
@@ -785,6 +1076,26 @@ impl Shard {
         }
     }
 
+    /// Resumes a session from a previously persisted [`SessionState`],
+    /// replacing the shard's current session.
+    ///
+    /// If Discord no longer considers the session valid, the shard receives
+    /// an `INVALID_SESSION` and identifies again as usual. Refer to
+    /// [`SessionState`] for how long a session can be resumed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the new WebSocket connection could not be opened,
+    /// or if the RESUME could not be sent.
+    #[instrument(skip(self, state))]
+    pub async fn resume_from(&mut self, state: SessionState) -> Result<()> {
+        self.session_id = Some(state.session_id);
+        self.seq = state.seq;
+        self.resume_ws_url = state.resume_gateway_url;
+
+        self.resume().await
+    }
+
     #[instrument(skip(self))]
     pub async fn reconnect(&mut self) -> Result<()> {
         info!("[Shard {:?}] Attempting to reconnect", self.shard_info());
@@ -795,19 +1106,248 @@ impl Shard {
         Ok(())
     }
 
+    /// Reconnects with payload compression disabled.
+    ///
+    /// This is used when the payloads received while connecting can not be
+    /// decompressed, such as when a proxy in between does not support
+    /// compressed payloads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the new WebSocket connection could not be opened.
+    #[instrument(skip(self))]
+    pub async fn reconnect_without_compression(&mut self) -> Result<()> {
+        warn!(
+            "[Shard {:?}] Failed to decompress payloads, reconnecting without compression",
+            self.shard_info
+        );
+
+        self.compress = false;
+
+        self.reconnect().await
+    }
+
+    /// Gracefully shuts down the WebSocket connection.
+    ///
+    /// This flushes any queued messages, sends a close frame with the given
+    /// `close_code`, and waits up to 5 seconds for Discord to acknowledge it,
+    /// so the disconnect is recorded as clean rather than as a dropped
+    /// connection. If no code is given, `1000` (normal closure) is used,
+    /// which also invalidates the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Tungstenite`] if the queued messages or the close
+    /// frame could not be sent.
+    #[instrument(skip(self))]
+    pub async fn shutdown(&mut self, close_code: Option<u16>) -> Result<()> {
+        const CLOSE_TIMEOUT: StdDuration = StdDuration::from_secs(5);
+
+        let close = CloseFrame {
+            code: close_code.unwrap_or(1000).into(),
+            reason: Cow::from(""),
+        };
+
+        self.client.flush().await?;
+        self.client.close(Some(close)).await?;
+        self.stage = ConnectionStage::Disconnected;
+
+        // Wait for the close frame in response, or for the connection to end.
+        let acknowledged = timeout(CLOSE_TIMEOUT, async {
+            while let Some(message) = self.client.next().await {
+                match message {
+                    Ok(Message::Close(_)) => break,
+                    Ok(_) => {},
+                    Err(why) => {
+                        debug!("[Shard {:?}] Err awaiting close frame: {:?}", self.shard_info, why);
+
+                        break;
+                    },
+                }
+            }
+        })
+        .await;
+
+        if acknowledged.is_err() {
+            warn!("[Shard {:?}] Timed out awaiting close frame", self.shard_info);
+        }
+
+        Ok(())
+    }
+
+    /// Sends the shard's current presence, as set with [`Self::set_presence`],
+    /// to the gateway.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GatewayError::InvalidActivity`] without sending anything if
+    /// the activity would be rejected by the gateway, such as because it has
+    /// more than 2 buttons. Otherwise returns an error if the presence update
+    /// could not be sent.
     #[instrument(skip(self))]
     pub async fn update_presence(&mut self) -> Result<()> {
+        if let Some(activity) = &self.current_presence.0 {
+            activity.validate_buttons().map_err(GatewayError::InvalidActivity)?;
+        }
+
+        self.command_ratelimiter.acquire().await;
         self.client.send_presence_update(&self.shard_info, &self.current_presence).await
     }
 }
 
-async fn connect(base_url: &str) -> Result<WsStream> {
-    let url =
-        Url::parse(&format!("{}?v={}", base_url, constants::GATEWAY_VERSION)).map_err(|why| {
+async fn connect(base_url: &str, config: &GatewayConfig) -> Result<WsStream> {
+    let url = GatewayUrl(base_url.to_string())
+        .append_version(constants::GATEWAY_VERSION.into())
+        .to_url()
+        .map_err(|why| {
             warn!("Error building gateway URL with base `{}`: {:?}", base_url, why);
 
             Error::Gateway(GatewayError::BuildingUrl)
         })?;
 
-    create_client(url).await
+    timeout(config.connect_timeout, create_client(url, config.max_frame_size))
+        .await
+        .map_err(|_| Error::Gateway(GatewayError::ConnectTimeout))?
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use futures::StreamExt;
+    use tokio::sync::Mutex;
+
+    use super::{connect, Shard};
+    use crate::gateway::{CommandRatelimiter, GatewayConfig, GatewayError, SessionState};
+    use crate::json::{self, json};
+    use crate::model::gateway::{Activity, ActivityButton, ActivityError};
+    use crate::Error;
+
+    #[tokio::test]
+    async fn test_connect_timeout() {
+        // Accepts TCP connections, but never completes the WebSocket
+        // handshake.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let config = GatewayConfig::default().connect_timeout(Duration::from_millis(100));
+        let result = connect(&url, &config).await;
+        assert!(matches!(result, Err(Error::Gateway(GatewayError::ConnectTimeout))));
+    }
+
+    #[tokio::test]
+    async fn test_hello_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            async_tungstenite::tokio::accept_async(stream).await.unwrap()
+        });
+
+        // The server completes the handshake, but never sends a Hello.
+        let config = GatewayConfig::default().hello_timeout(Duration::from_millis(100));
+        let ws_url = Arc::new(Mutex::new(url));
+        let mut shard = Shard::with_config(ws_url, "token", [0, 1], config).await.unwrap();
+        let _server = server.await.unwrap();
+
+        assert!(shard.check_heartbeat().await);
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+
+        // The runner restarts the shard when this is false.
+        assert!(!shard.check_heartbeat().await);
+    }
+
+    #[tokio::test]
+    async fn test_identify_capabilities() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = async_tungstenite::tokio::accept_async(stream).await.unwrap();
+
+            let mut identify = ws.next().await.unwrap().unwrap().into_text().unwrap();
+            json::from_str::<json::Value>(identify.as_mut_str()).unwrap()
+        });
+
+        let config = GatewayConfig::default().capabilities(16381);
+        let ws_url = Arc::new(Mutex::new(url));
+        let mut shard = Shard::with_config(ws_url, "token", [0, 1], config).await.unwrap();
+        shard.identify().await.unwrap();
+
+        let identify = server.await.unwrap();
+        assert_eq!(identify["op"], 2);
+        assert_eq!(identify["d"]["capabilities"], 16381);
+    }
+
+    #[test]
+    fn test_session_state_serde() {
+        let mut state = SessionState::new("session", 42);
+        state.resume_gateway_url = Some("wss://gateway-us-east1-b.discord.gg".to_string());
+
+        let value = json::to_value(&state).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "session_id": "session",
+                "seq": 42,
+                "resume_gateway_url": "wss://gateway-us-east1-b.discord.gg",
+            })
+        );
+        assert_eq!(json::from_value::<SessionState>(value).unwrap(), state);
+
+        let state: SessionState =
+            json::from_value(json!({"session_id": "session", "seq": 42})).unwrap();
+        assert_eq!(state, SessionState::new("session", 42));
+    }
+
+    #[tokio::test]
+    async fn test_update_presence_validates_activity() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            async_tungstenite::tokio::accept_async(stream).await.unwrap()
+        });
+
+        let ws_url = Arc::new(Mutex::new(url));
+        let mut shard = Shard::new(ws_url, "token", [0, 1]).await.unwrap();
+        let _server = server.await.unwrap();
+
+        let mut activity = Activity::playing("Game");
+        activity.buttons = vec![ActivityButton::new("Join", "https://example.com"); 3];
+        shard.set_activity(Some(activity));
+
+        let result = shard.update_presence().await;
+        assert!(matches!(
+            result,
+            Err(Error::Gateway(GatewayError::InvalidActivity(ActivityError::TooManyButtons(3))))
+        ));
+        // Nothing was sent, so the command budget is untouched.
+        assert_eq!(shard.command_ratelimiter().remaining(), CommandRatelimiter::BUDGET);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_write_failure() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            async_tungstenite::tokio::accept_async(stream).await.unwrap()
+        });
+
+        let ws_url = Arc::new(Mutex::new(url));
+        let mut shard = Shard::new(ws_url, "token", [0, 1]).await.unwrap();
+        let _server = server.await.unwrap();
+
+        // A heartbeat is due, and the connection can no longer be written to.
+        shard.heartbeat_interval = Some(1000);
+        shard.heartbeat_instants.0 = Instant::now().checked_sub(Duration::from_secs(2));
+        shard.client.close(None).await.unwrap();
+
+        assert!(!shard.check_heartbeat().await);
+        assert_eq!(shard.heartbeat_failures(), 1);
+    }
 }
@@ -1,16 +1,18 @@
 use std::env::consts;
-use std::time::SystemTime;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
-use tracing::{debug, instrument, trace};
+use tracing::{debug, instrument, trace, warn};
 
 use crate::client::bridge::gateway::ChunkGuildFilter;
 use crate::constants::{self, OpCode};
-use crate::gateway::{CurrentPresence, WsStream};
+use crate::gateway::{CurrentPresence, GatewayConfig, WsStream, MAX_NONCE_LENGTH};
 use crate::internal::prelude::*;
 use crate::internal::ws_impl::SenderExt;
-use crate::json::json;
+use crate::json::{self, json};
+use crate::model::gateway::{Activity, OutboundPresence, PresenceUpdatePayload};
 use crate::model::id::GuildId;
+use crate::model::user::OnlineStatus;
 
 #[async_trait]
 pub trait WebSocketGatewayClientExt {
@@ -21,15 +23,31 @@ pub trait WebSocketGatewayClientExt {
         limit: Option<u16>,
         filter: ChunkGuildFilter,
         nonce: Option<&str>,
+        presences: bool,
     ) -> Result<()>;
 
     async fn send_heartbeat(&mut self, shard_info: &[u64; 2], seq: Option<u64>) -> Result<()>;
 
-    async fn send_identify(
+    async fn send_identify(&mut self, shard_info: &[u64; 2], token: &str) -> Result<()>;
+
+    /// Identifies like [`Self::send_identify`], with the capabilities and
+    /// client build number of the given `config`, and with payload
+    /// compression enabled as given.
+    ///
+    /// Implementors not overriding this ignore the settings, and identify with
+    /// [`Self::send_identify`].
+    async fn send_identify_with_config(
         &mut self,
         shard_info: &[u64; 2],
-        token: &str
-    ) -> Result<()>;
+        token: &str,
+        _compress: bool,
+        _config: &GatewayConfig,
+    ) -> Result<()>
+    where
+        Self: Send,
+    {
+        self.send_identify(shard_info, token).await
+    }
 
     async fn send_presence_update(
         &mut self,
@@ -56,15 +74,24 @@ impl WebSocketGatewayClientExt for WsStream {
         limit: Option<u16>,
         filter: ChunkGuildFilter,
         nonce: Option<&str>,
+        presences: bool,
     ) -> Result<()> {
         debug!("[Shard {:?}] Requesting member chunks", shard_info);
 
+        if nonce.map_or(0, |nonce| nonce.chars().count()) > MAX_NONCE_LENGTH {
+            warn!(
+                "[Shard {:?}] Nonce is longer than {} characters and will be ignored by Discord",
+                shard_info, MAX_NONCE_LENGTH
+            );
+        }
+
         let mut payload = json!({
             "op": OpCode::GetGuildMembers.num(),
             "d": {
                 "guild_id": guild_id.as_ref().0.to_string(),
                 "limit": limit.unwrap_or(0),
                 "nonce": nonce.unwrap_or(""),
+                "presences": presences,
             },
         });
 
@@ -93,38 +120,21 @@ impl WebSocketGatewayClientExt for WsStream {
     }
 
     #[instrument(skip(self, token))]
-    async fn send_identify(
+    async fn send_identify(&mut self, shard_info: &[u64; 2], token: &str) -> Result<()> {
+        self.send_identify_with_config(shard_info, token, true, &GatewayConfig::default()).await
+    }
+
+    #[instrument(skip(self, token, config))]
+    async fn send_identify_with_config(
         &mut self,
         shard_info: &[u64; 2],
-        token: &str
+        token: &str,
+        compress: bool,
+        config: &GatewayConfig,
     ) -> Result<()> {
         debug!("[Shard {:?}] Identifying", shard_info);
 
-        self.send_json(&json!({
-            "op": OpCode::Identify.num(),
-            "d": {
-                "compress": true,
-                "token": token,
-                "v": constants::GATEWAY_VERSION,
-                "properties": {
-                    "browser": "Firefox",
-                    "device": "",
-                    "system_locale": "en-US",
-                    "browser_user_agent": "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/109.0",
-                    "browser_version": "113.0",
-                    "os": "Linux",
-                    "os_version": "",
-                    "referrer": "",
-                    "referring_domain": "",
-                    "referrer_current": "",
-                    "referring_domain_current": "",
-                    "release_channel": "stable",
-                    "client_build_number": 193906,
-                    "design_id": 0
-                },
-            },
-        }))
-        .await
+        self.send_json(&identify_payload(token, compress, config)).await
     }
 
     #[instrument(skip(self))]
@@ -133,25 +143,9 @@ impl WebSocketGatewayClientExt for WsStream {
         shard_info: &[u64; 2],
         current_presence: &CurrentPresence,
     ) -> Result<()> {
-        let (activity, status) = current_presence;
-        let now = SystemTime::now();
-
         debug!("[Shard {:?}] Sending presence update", shard_info);
 
-        self.send_json(&json!({
-            "op": OpCode::StatusUpdate.num(),
-            "d": {
-                "afk": false,
-                "since": now,
-                "status": status.name(),
-                "game": activity.as_ref().map(|x| json!({
-                    "name": x.name,
-                    "type": x.kind,
-                    "url": x.url,
-                })),
-            },
-        }))
-        .await
+        self.send_json(&current_presence_update_payload(current_presence)).await
     }
 
     #[instrument(skip(self, token))]
@@ -176,3 +170,199 @@ impl WebSocketGatewayClientExt for WsStream {
         .map_err(From::from)
     }
 }
+
+/// Builds the identify payload, with the capabilities and client build
+/// number of the given `config`.
+fn identify_payload(token: &str, compress: bool, config: &GatewayConfig) -> Value {
+    json!({
+        "op": OpCode::Identify.num(),
+        "d": {
+            "capabilities": config.capabilities,
+            "compress": compress,
+            "token": token,
+            "v": constants::GATEWAY_VERSION,
+            "properties": {
+                "browser": "Firefox",
+                "device": "",
+                "system_locale": "en-US",
+                "browser_user_agent": "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/109.0",
+                "browser_version": "113.0",
+                "os": "Linux",
+                "os_version": "",
+                "referrer": "",
+                "referring_domain": "",
+                "referrer_current": "",
+                "referring_domain_current": "",
+                "release_channel": "stable",
+                "client_build_number": config.client_build_number,
+                "design_id": 0
+            },
+        },
+    })
+}
+
+/// Builds the presence update payload for the current presence of a shard.
+fn current_presence_update_payload(current_presence: &CurrentPresence) -> Value {
+    let (activity, status) = current_presence;
+
+    // Only relevant while idle; Discord expects 0 otherwise.
+    let since = match status {
+        OnlineStatus::Idle => {
+            SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+        },
+        _ => 0,
+    };
+
+    presence_update_payload(activity.as_ref(), *status, false, Some(since))
+}
+
+/// Builds the payload of a presence update, gateway opcode 3, which can be
+/// sent as-is over a gateway connection.
+///
+/// `since` is the Unix time in milliseconds of when the client went idle. The
+/// payload has the following structure, with every field of the activity,
+/// such as its buttons, assets and emoji:
+///
+/// ```json
+/// {
+///     "op": 3,
+///     "d": {
+///         "afk": false,
+///         "since": 1650000000000,
+///         "status": "idle",
+///         "activities": [{
+///             "name": "Arena",
+///             "type": 5,
+///             "details": "Round 2",
+///             "state": "Finals",
+///             ...
+///         }]
+///     }
+/// }
+/// ```
+///
+/// The activity list is always sent, so that an empty one clears any activity
+/// set previously, including a custom status.
+///
+/// This is the same as serializing a [`PresenceUpdatePayload`].
+///
+/// [`PresenceUpdatePayload`]: crate::model::gateway::PresenceUpdatePayload
+#[must_use]
+#[allow(clippy::unwrap_used)] // serializing the payload can not fail, as all its maps have string keys
+pub fn presence_update_payload(
+    activity: Option<&Activity>,
+    status: OnlineStatus,
+    afk: bool,
+    since: Option<u64>,
+) -> Value {
+    let presence = OutboundPresence {
+        since,
+        activities: activity.cloned().into_iter().collect(),
+        status,
+        afk,
+    };
+
+    json::to_value(PresenceUpdatePayload::new(presence)).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{current_presence_update_payload, identify_payload, presence_update_payload};
+    use crate::constants;
+    use crate::gateway::GatewayConfig;
+    use crate::json::{self, json};
+    use crate::model::gateway::{Activity, ActivityAssets, ActivityButton};
+    use crate::model::user::OnlineStatus;
+
+    #[test]
+    fn test_identify_payload() {
+        let payload = identify_payload("token", true, &GatewayConfig::default());
+        assert_eq!(payload["op"], 2);
+        assert_eq!(payload["d"]["token"], "token");
+        assert_eq!(payload["d"]["compress"], true);
+        assert_eq!(payload["d"]["capabilities"], constants::IDENTIFY_CAPABILITIES);
+        assert_eq!(
+            payload["d"]["properties"]["client_build_number"],
+            constants::CLIENT_BUILD_NUMBER
+        );
+
+        let config = GatewayConfig::default().capabilities(16381).client_build_number(200_000);
+        let payload = identify_payload("token", false, &config);
+        assert_eq!(payload["d"]["compress"], false);
+        assert_eq!(payload["d"]["capabilities"], 16381);
+        assert_eq!(payload["d"]["properties"]["client_build_number"], 200_000);
+    }
+
+    #[test]
+    fn test_reset_presence_payload() {
+        let payload = current_presence_update_payload(&(None, OnlineStatus::Online));
+
+        assert_eq!(
+            payload,
+            json!({
+                "op": 3,
+                "d": {
+                    "afk": false,
+                    "since": 0,
+                    "status": "online",
+                    "activities": [],
+                },
+            })
+        );
+
+        let payload = current_presence_update_payload(&(
+            Some(Activity::playing("Game")),
+            OnlineStatus::Online,
+        ));
+        assert_eq!(payload["d"]["activities"][0]["name"], "Game");
+
+        let activity = Activity::competing("Arena").with_details("Round 2").with_state("Finals");
+        let payload = current_presence_update_payload(&(Some(activity), OnlineStatus::Online));
+        let activity = &payload["d"]["activities"][0];
+        assert_eq!(activity["name"], "Arena");
+        assert_eq!(activity["type"], 5);
+        assert_eq!(activity["details"], "Round 2");
+        assert_eq!(activity["state"], "Finals");
+    }
+
+    #[test]
+    fn test_presence_update_payload() {
+        let activity = Activity::playing("Game");
+        let payload =
+            presence_update_payload(Some(&activity), OnlineStatus::Idle, true, Some(1_650_000));
+
+        assert_eq!(payload["op"], 3);
+        assert_eq!(payload["d"]["afk"], true);
+        assert_eq!(payload["d"]["since"], 1_650_000);
+        assert_eq!(payload["d"]["status"], "idle");
+        assert_eq!(payload["d"]["activities"], json!([json::to_value(&activity).unwrap()]));
+
+        let payload = presence_update_payload(None, OnlineStatus::DoNotDisturb, false, None);
+        assert_eq!(payload["d"]["since"], json!(null));
+        assert_eq!(payload["d"]["activities"], json!([]));
+    }
+
+    #[test]
+    fn test_presence_update_payload_full_activity() {
+        let assets = ActivityAssets::builder().large_image("map").large_text("Map").build().unwrap();
+        let mut activity = Activity::playing("Game")
+            .with_buttons(vec![ActivityButton::new("Join", "https://example.com")])
+            .unwrap();
+        activity.assets = Some(assets);
+
+        let payload = presence_update_payload(Some(&activity), OnlineStatus::Online, false, None);
+        let sent = &payload["d"]["activities"][0];
+        assert_eq!(sent["buttons"], json::to_value(&activity.buttons).unwrap());
+        assert_eq!(sent["assets"], json::to_value(&activity.assets).unwrap());
+
+        let status: Activity = json::from_value(json!({
+            "type": 4,
+            "name": "Custom Status",
+            "state": "Busy",
+            "emoji": {"name": "\u{1F4DA}"},
+        }))
+        .unwrap();
+        let payload = presence_update_payload(Some(&status), OnlineStatus::Online, false, None);
+        assert_eq!(payload["d"]["activities"][0]["emoji"]["name"], "\u{1F4DA}");
+    }
+}
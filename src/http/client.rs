@@ -6,11 +6,13 @@ use std::fmt;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use reqwest::header::{HeaderMap as Headers, HeaderValue, CONTENT_TYPE};
 use reqwest::{Client, ClientBuilder, Response as ReqwestResponse, StatusCode, Url};
 use serde::de::DeserializeOwned;
+use tokio::sync::RwLock;
 use tracing::{debug, instrument, trace};
 
 use super::multipart::Multipart;
@@ -18,6 +20,7 @@ use super::ratelimiting::{RatelimitedRequest, Ratelimiter};
 use super::request::Request;
 use super::routing::RouteInfo;
 use super::typing::Typing;
+use super::utils::{find_build_number, script_paths};
 use super::{AttachmentType, GuildPagination, HttpError, UserPagination};
 use crate::internal::prelude::*;
 use crate::json::prelude::*;
@@ -167,6 +170,8 @@ impl HttpBuilder {
             proxy: self.proxy,
             token,
             application_id,
+            bot_gateway_cache: RwLock::new(None),
+            client_build_number_cache: RwLock::new(None),
         }
     }
 }
@@ -199,6 +204,8 @@ pub struct Http {
     pub proxy: Option<Url>,
     pub token: String,
     application_id: AtomicU64,
+    bot_gateway_cache: RwLock<Option<(BotGateway, Instant)>>,
+    client_build_number_cache: RwLock<Option<(u64, Instant)>>,
 }
 
 impl fmt::Debug for Http {
@@ -229,6 +236,8 @@ impl Http {
             proxy: None,
             token,
             application_id: AtomicU64::new(0),
+            bot_gateway_cache: RwLock::new(None),
+            client_build_number_cache: RwLock::new(None),
         }
     }
 
@@ -2499,6 +2508,108 @@ impl Http {
         .await
     }
 
+    /// Gets current bot gateway, reusing the last response if it is no older
+    /// than `max_age`.
+    ///
+    /// The bot gateway endpoint is limited to about one request every 5
+    /// seconds, so a `max_age` of 5 seconds avoids hitting the limit during
+    /// rapid reconnects.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Self::get_bot_gateway`] when the cached
+    /// response is missing or older than `max_age`. Failed requests are not
+    /// cached.
+    pub async fn get_bot_gateway_cached(&self, max_age: Duration) -> Result<BotGateway> {
+        if let Some((gateway, fetched_at)) = &*self.bot_gateway_cache.read().await {
+            if fetched_at.elapsed() <= max_age {
+                return Ok(gateway.clone());
+            }
+        }
+
+        let mut cache = self.bot_gateway_cache.write().await;
+
+        // Another task may have refreshed the cache while waiting for the lock.
+        if let Some((gateway, fetched_at)) = &*cache {
+            if fetched_at.elapsed() <= max_age {
+                return Ok(gateway.clone());
+            }
+        }
+
+        let gateway = self.get_bot_gateway().await?;
+        *cache = Some((gateway.clone(), Instant::now()));
+
+        Ok(gateway)
+    }
+
+    /// Fetches the build number of the current release of Discord's web
+    /// client, which user accounts send when identifying.
+    ///
+    /// The build number is searched for in the scripts of the web client,
+    /// starting with the last one loaded.
+    ///
+    /// **Note**: This relies on the structure of the web client, which may
+    /// change at any time.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HttpError::Request`] if a page of the web client could not
+    /// be retrieved, or [`HttpError::ClientBuildNumberNotFound`] if none of
+    /// its scripts contain a build number.
+    pub async fn fetch_client_build_number(&self) -> Result<u64> {
+        let html = self.fetch_client_page(constants::CLIENT_APP_URL).await?;
+        let base = Url::parse(constants::CLIENT_APP_URL).map_err(HttpError::Url)?;
+
+        for path in script_paths(&html).into_iter().rev() {
+            let url = base.join(path).map_err(HttpError::Url)?;
+            let script = self.fetch_client_page(url.as_str()).await?;
+
+            if let Some(build_number) = find_build_number(&script) {
+                debug!("Found client build number {} in {}", build_number, url);
+
+                return Ok(build_number);
+            }
+        }
+
+        Err(HttpError::ClientBuildNumberNotFound.into())
+    }
+
+    /// Fetches the build number of the current release of Discord's web
+    /// client, reusing the last one fetched if it is no older than `max_age`.
+    ///
+    /// Refer to [`Self::fetch_client_build_number`] for more info.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Self::fetch_client_build_number`].
+    pub async fn fetch_client_build_number_cached(&self, max_age: Duration) -> Result<u64> {
+        if let Some((build_number, fetched_at)) = *self.client_build_number_cache.read().await {
+            if fetched_at.elapsed() <= max_age {
+                return Ok(build_number);
+            }
+        }
+
+        let mut cache = self.client_build_number_cache.write().await;
+
+        // Another task may have refreshed the cache while waiting for the lock.
+        if let Some((build_number, fetched_at)) = *cache {
+            if fetched_at.elapsed() <= max_age {
+                return Ok(build_number);
+            }
+        }
+
+        let build_number = self.fetch_client_build_number().await?;
+        *cache = Some((build_number, Instant::now()));
+
+        Ok(build_number)
+    }
+
+    async fn fetch_client_page(&self, url: &str) -> Result<String> {
+        let response = self.client.get(url).send().await?.error_for_status()?;
+
+        Ok(response.text().await?)
+    }
+
     /// Gets all invites for a channel.
     pub async fn get_channel_invites(&self, channel_id: u64) -> Result<Vec<RichInvite>> {
         self.fire(Request {
@@ -78,6 +78,9 @@ pub enum Error {
     InvalidPort,
     /// When an application id was expected but missing.
     ApplicationIdMissing,
+    /// When the client build number could not be found in the scripts of
+    /// the web client.
+    ClientBuildNumberNotFound,
 }
 
 impl Error {
@@ -172,6 +175,9 @@ impl fmt::Display for Error {
             Self::InvalidScheme => f.write_str("Invalid Url scheme."),
             Self::InvalidPort => f.write_str("Invalid port."),
             Self::ApplicationIdMissing => f.write_str("Application id was expected but missing."),
+            Self::ClientBuildNumberNotFound => {
+                f.write_str("Client build number was not found in the web client.")
+            },
         }
     }
 }
@@ -62,3 +62,62 @@ fn loop_errors(value: &Value, errors: &mut Vec<DiscordJsonSingleError>, path: &[
         loop_errors(looped, errors, &new_path);
     }
 }
+
+/// Returns the paths of the scripts of a page of the web client, in the order
+/// they are loaded.
+// The paths of the web client's assets are generated, and always lowercase.
+#[allow(clippy::case_sensitive_file_extension_comparisons)]
+pub fn script_paths(html: &str) -> Vec<&str> {
+    html.split("<script")
+        .skip(1)
+        .filter_map(|tag| {
+            let tag = &tag[..tag.find('>')?];
+            let src = &tag[tag.find("src=\"")? + 5..];
+            let src = &src[..src.find('"')?];
+
+            if src.starts_with("/assets/") && src.ends_with(".js") {
+                Some(src)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Finds the build number embedded in a script of the web client.
+pub fn find_build_number(script: &str) -> Option<u64> {
+    ["buildNumber:\"", "build_number:\""].iter().find_map(|marker| {
+        let digits = &script[script.find(marker)? + marker.len()..];
+        let end = digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+
+        digits[..end].parse().ok()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_build_number, script_paths};
+
+    #[test]
+    fn test_script_paths() {
+        let html = r#"<html><head>
+            <script nonce="abc">window.GLOBAL_ENV = {};</script>
+            <script src="/assets/web.1a2b3c.js" defer></script>
+            <script src="https://example.com/tracker.js"></script>
+            <script defer src="/assets/sentry.4d5e6f.js"></script>
+        </head></html>"#;
+
+        assert_eq!(script_paths(html), vec!["/assets/web.1a2b3c.js", "/assets/sentry.4d5e6f.js"]);
+    }
+
+    #[test]
+    fn test_find_build_number() {
+        assert_eq!(find_build_number(r#"e={buildNumber:"193906",version:"a"}"#), Some(193906));
+        assert_eq!(
+            find_build_number(r#"{release_channel:"stable",build_number:"200000"}"#),
+            Some(200000)
+        );
+        assert_eq!(find_build_number(r#"buildNumber:""#), None);
+        assert_eq!(find_build_number("function(){}"), None);
+    }
+}
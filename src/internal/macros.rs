@@ -42,8 +42,20 @@ macro_rules! feature_cache {
     }};
 }
 
+/// The macro implements (de)serialization of a C-like enum as its integer
+/// value, mapping unlisted values to the enum's `Unknown` variant.
+///
+/// Every variant other than `Unknown` must be listed, or the exhaustiveness
+/// check below fails to compile.
 macro_rules! enum_number {
     ($name:ident { $($(#[$attr:meta])? $variant:ident $(,)? )* }) => {
+        // Ensures that a variant added to the enum is not forgotten here, which
+        // would otherwise silently (de)serialize it as `Unknown`.
+        const _: fn($name) = |value| match value {
+            $( $(#[$attr])? $name::$variant => {}, )*
+            $name::Unknown => {},
+        };
+
         impl $name {
             #[inline]
             #[must_use]
@@ -14,7 +14,8 @@ use crate::json::{from_str, to_string};
 
 #[async_trait]
 pub trait ReceiverExt {
-    async fn recv_json(&mut self) -> Result<Option<Value>>;
+    /// Receives a payload, decompressing it to at most `max_size` bytes.
+    async fn recv_json(&mut self, max_size: usize) -> Result<Option<Value>>;
 }
 
 #[async_trait]
@@ -24,7 +25,7 @@ pub trait SenderExt {
 
 #[async_trait]
 impl ReceiverExt for WsStream {
-    async fn recv_json(&mut self) -> Result<Option<Value>> {
+    async fn recv_json(&mut self, max_size: usize) -> Result<Option<Value>> {
         const TIMEOUT: tokio::time::Duration = tokio::time::Duration::from_millis(500);
 
         let ws_message = match timeout(TIMEOUT, self.next()).await {
@@ -33,7 +34,7 @@ impl ReceiverExt for WsStream {
             Ok(None) | Err(_) => None,
         };
 
-        convert_ws_message(ws_message)
+        convert_ws_message(ws_message, max_size)
     }
 }
 
@@ -45,17 +46,38 @@ impl SenderExt for WsStream {
 }
 
 #[inline]
-pub(crate) fn convert_ws_message(message: Option<Message>) -> Result<Option<Value>> {
+pub(crate) fn convert_ws_message(
+    message: Option<Message>,
+    max_size: usize,
+) -> Result<Option<Value>> {
     const DECOMPRESSION_MULTIPLIER: usize = 3;
 
     Ok(match message {
         Some(Message::Binary(bytes)) => {
-            let mut decompressed = String::with_capacity(bytes.len() * DECOMPRESSION_MULTIPLIER);
+            let capacity = (bytes.len() * DECOMPRESSION_MULTIPLIER).min(max_size);
+            let mut decompressed = Vec::with_capacity(capacity);
 
-            ZlibDecoder::new(&bytes[..]).read_to_string(&mut decompressed).map_err(|why| {
+            // Reading one byte past the maximum is enough to tell whether the
+            // payload is too large, without inflating all of it.
+            let limit = max_size as u64 + 1;
+            ZlibDecoder::new(&bytes[..]).take(limit).read_to_end(&mut decompressed).map_err(
+                |why| {
+                    warn!("Err decompressing bytes: {:?}; bytes: {:?}", why, bytes);
+
+                    Error::Gateway(GatewayError::DecompressionFailed)
+                },
+            )?;
+
+            if decompressed.len() > max_size {
+                warn!("Decompressed payload exceeds the maximum size of {} bytes", max_size);
+
+                return Err(Error::Gateway(GatewayError::PayloadTooLarge));
+            }
+
+            let mut decompressed = String::from_utf8(decompressed).map_err(|why| {
                 warn!("Err decompressing bytes: {:?}; bytes: {:?}", why, bytes);
 
-                why
+                Error::Gateway(GatewayError::DecompressionFailed)
             })?;
 
             from_str(decompressed.as_mut_str()).map(Some).map_err(|why| {
@@ -78,10 +100,10 @@ pub(crate) fn convert_ws_message(message: Option<Message>) -> Result<Option<Valu
 }
 
 #[instrument]
-pub(crate) async fn create_client(url: Url) -> Result<WsStream> {
+pub(crate) async fn create_client(url: Url, max_frame_size: usize) -> Result<WsStream> {
     let config = async_tungstenite::tungstenite::protocol::WebSocketConfig {
-        max_message_size: None,
-        max_frame_size: None,
+        max_message_size: Some(max_frame_size),
+        max_frame_size: Some(max_frame_size),
         max_send_queue: None,
         accept_unmasked_frames: false,
     };
@@ -90,3 +112,40 @@ pub(crate) async fn create_client(url: Url) -> Result<WsStream> {
 
     Ok(stream)
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use async_tungstenite::tungstenite::Message;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    use super::convert_ws_message;
+    use crate::gateway::GatewayError;
+    use crate::json::json;
+    use crate::Error;
+
+    fn compress(payload: &str) -> Message {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(payload.as_bytes()).unwrap();
+
+        Message::Binary(encoder.finish().unwrap())
+    }
+
+    #[test]
+    fn test_decompressed_size_limit() {
+        let payload = format!(r#"{{"op":11,"d":"{}"}}"#, "a".repeat(4096));
+
+        // Compresses to far less than the limit, but inflates past it.
+        let message = compress(&payload);
+        assert!(matches!(&message, Message::Binary(bytes) if bytes.len() < 1024));
+        assert!(matches!(
+            convert_ws_message(Some(message), 1024),
+            Err(Error::Gateway(GatewayError::PayloadTooLarge))
+        ));
+
+        let value = convert_ws_message(Some(compress(&payload)), payload.len()).unwrap();
+        assert_eq!(value, Some(json!({"op": 11, "d": "a".repeat(4096)})));
+    }
+}
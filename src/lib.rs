@@ -106,6 +106,8 @@ pub mod framework;
 pub mod gateway;
 #[cfg(feature = "http")]
 pub mod http;
+#[cfg(feature = "test_util")]
+pub mod test_util;
 #[cfg(feature = "utils")]
 pub mod utils;
 
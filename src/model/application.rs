@@ -13,7 +13,7 @@ use super::Permissions;
 /// Partial information about the given application.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct PartialCurrentApplicationInfo {
-    /// The unique Id of the user.
+    /// The unique Id of the application.
     pub id: ApplicationId,
     /// The flags associated with the application.
     pub flags: ApplicationFlags,
@@ -197,6 +197,5 @@ pub enum InputTextStyle {
 
 enum_number!(InputTextStyle {
     Short,
-    Paragraph,
-    Unknown
+    Paragraph
 });
@@ -10,12 +10,60 @@ use super::application::component::ActionRow;
 use super::prelude::*;
 use super::utils::{emojis, roles, stickers};
 use crate::constants::OpCode;
+#[cfg(feature = "model")]
+use crate::http::Http;
 use crate::internal::prelude::*;
 use crate::json::prelude::*;
 use crate::model::application::command::CommandPermission;
 use crate::model::application::interaction::Interaction;
 use crate::model::guild::automod::{ActionExecution, Rule};
 
+/// A user has asked to join the current user's activity party.
+///
+/// The request can be accepted with [`Self::accept`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct ActivityJoinRequestEvent {
+    /// The user requesting to join.
+    pub user: PresenceUser,
+    /// The Id of the application the activity belongs to.
+    #[serde(default)]
+    pub application_id: Option<ApplicationId>,
+}
+
+#[cfg(feature = "model")]
+impl ActivityJoinRequestEvent {
+    /// Accepts the request, sending the user an invite to join the party with
+    /// the given Id in their direct message channel.
+    ///
+    /// The party Id is the one set in the current user's [`ActivityParty`].
+    /// Declining a request does not require sending anything.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Http`] if the direct message channel could not be
+    /// opened or the invite could not be sent.
+    ///
+    /// [`ActivityParty`]: crate::model::gateway::ActivityParty
+    pub async fn accept(&self, http: impl AsRef<Http>, party_id: &str) -> Result<Message> {
+        let http = http.as_ref();
+        let channel = self.user.id.create_dm_channel(http).await?;
+
+        http.send_message(channel.id.0, &self.join_invite_payload(party_id)).await
+    }
+
+    fn join_invite_payload(&self, party_id: &str) -> Value {
+        json!({
+            "content": "",
+            "application_id": self.application_id.map(|id| id.0.to_string()),
+            "activity": {
+                "type": MessageActivityKind::JOIN,
+                "party_id": party_id,
+            },
+        })
+    }
+}
+
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway#application-command-permissions-update).
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(transparent)]
@@ -189,7 +237,10 @@ pub struct GuildMembersChunkEvent {
     pub members: HashMap<UserId, Member>,
     pub chunk_index: u32,
     pub chunk_count: u32,
+    /// The nonce of the request the chunk is a response to, if it had one.
     pub nonce: Option<String>,
+    /// The presences of the members, if they were requested.
+    pub presences: Option<Vec<Presence>>,
 }
 
 impl<'de> Deserialize<'de> for GuildMembersChunkEvent {
@@ -202,6 +253,7 @@ impl<'de> Deserialize<'de> for GuildMembersChunkEvent {
             ChunkCount,
             Members,
             Nonce,
+            Presences,
             Unknown(String),
         }
 
@@ -220,6 +272,7 @@ impl<'de> Deserialize<'de> for GuildMembersChunkEvent {
                 let mut chunk_count = None;
                 let mut members = None;
                 let mut nonce = None;
+                let mut presences = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
@@ -253,6 +306,12 @@ impl<'de> Deserialize<'de> for GuildMembersChunkEvent {
                             }
                             nonce = Some(map.next_value()?);
                         },
+                        Field::Presences => {
+                            if presences.is_some() {
+                                return Err(DeError::duplicate_field("presences"));
+                            }
+                            presences = Some(map.next_value()?);
+                        },
                         Field::Unknown(_) => {
                             // ignore unknown keys
                             map.next_value::<IgnoredAny>()?;
@@ -282,11 +341,13 @@ impl<'de> Deserialize<'de> for GuildMembersChunkEvent {
                     chunk_index,
                     chunk_count,
                     nonce,
+                    presences,
                 })
             }
         }
 
-        const FIELDS: &[&str] = &["guild_id", "chunk_index", "chunk_count", "members", "nonce"];
+        const FIELDS: &[&str] =
+            &["guild_id", "chunk_index", "chunk_count", "members", "nonce", "presences"];
         deserializer.deserialize_struct("GuildMembersChunkEvent", FIELDS, GuildMembersChunkVisitor)
     }
 }
@@ -438,6 +499,14 @@ pub struct MessageUpdateEvent {
 #[non_exhaustive]
 pub struct PresenceUpdateEvent {
     pub presence: Presence,
+    /// Whether this update was replayed by the gateway while resuming a
+    /// session.
+    ///
+    /// After a RESUME is sent, the gateway replays the events missed while
+    /// disconnected, followed by a RESUMED event. Presence updates received in
+    /// between are marked as replays.
+    #[serde(skip)]
+    pub is_resume_replay: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -447,6 +516,77 @@ pub struct PresencesReplaceEvent {
     pub presences: Vec<Presence>,
 }
 
+/// A change to a user's presence, dispatched to
+/// [`EventHandler::presence_event`].
+///
+/// A single presence update may result in several of these events, which are
+/// derived from the update by [`Self::from_update`].
+///
+/// [`EventHandler::presence_event`]: crate::client::EventHandler::presence_event
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum PresenceEvent {
+    /// A user's presence was updated. This is dispatched for every presence
+    /// update, and tells whether the update was replayed while resuming.
+    Updated(PresenceUpdateEvent),
+    /// The data of the user a presence belongs to changed, such as their name
+    /// or avatar.
+    UserUpdated {
+        /// The user's previous data, if known.
+        old: Option<PresenceUser>,
+        /// The user's new data.
+        new: PresenceUser,
+    },
+    /// An activity started or ended, by kind and name.
+    ActivityChanged {
+        /// The Id of the user whose activities changed.
+        user_id: UserId,
+        /// The user's previous activities.
+        old_activities: Vec<Activity>,
+        /// The user's new activities.
+        new_activities: Vec<Activity>,
+    },
+}
+
+impl PresenceEvent {
+    /// Derives the events resulting from a presence update, given the user's
+    /// previous presence if known.
+    ///
+    /// [`Self::Updated`] always comes first. Without a previous presence, such
+    /// as when the `cache` feature is disabled, [`Self::ActivityChanged`] is
+    /// never returned, and [`Self::UserUpdated`] is returned whenever the
+    /// update includes the user's data, which Discord only sends when it
+    /// changed.
+    #[must_use]
+    pub fn from_update(old: Option<&Presence>, update: &PresenceUpdateEvent) -> Vec<Self> {
+        let new = &update.presence;
+        let mut events = vec![Self::Updated(update.clone())];
+
+        let change = old.map(|old| new.changed_from(old));
+        let user_data_changed =
+            change.map_or_else(|| new.user.to_user().is_some(), |c| c.user_data_changed);
+
+        if user_data_changed {
+            events.push(Self::UserUpdated {
+                old: old.map(|old| old.user.clone()),
+                new: new.user.clone(),
+            });
+        }
+
+        if let (Some(old), Some(change)) = (old, change) {
+            if change.activities_changed {
+                events.push(Self::ActivityChanged {
+                    user_id: new.user.id,
+                    old_activities: old.activities.clone(),
+                    new_activities: new.activities.clone(),
+                });
+            }
+        }
+
+        events
+    }
+}
+
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway#message-reaction-add).
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(transparent)]
@@ -817,6 +957,12 @@ impl<'de> Deserialize<'de> for GatewayEvent {
 #[non_exhaustive]
 #[serde(untagged)]
 pub enum Event {
+    /// A user has asked to join the current user's activity party.
+    ///
+    /// Fires the [`EventHandler::activity_join_request`] event.
+    ///
+    /// [`EventHandler::activity_join_request`]: crate::client::EventHandler::activity_join_request
+    ActivityJoinRequest(ActivityJoinRequestEvent),
     /// The permissions of an [`Command`] was changed.
     ///
     /// Fires the [`EventHandler::application_command_permissions_update`] event.
@@ -1005,6 +1151,12 @@ fn gid_from_channel(c: &Channel) -> RelatedId<GuildId> {
 macro_rules! with_related_ids_for_event_types {
     ($macro:ident) => {
         $macro! {
+            Self::ActivityJoinRequest, Self::ActivityJoinRequest(e) => {
+                user_id: Some(e.user.id),
+                guild_id: Never,
+                channel_id: Never,
+                message_id: Never,
+            },
             Self::ApplicationCommandPermissionsUpdate, Self::ApplicationCommandPermissionsUpdate(e) => {
                 user_id: Never,
                 guild_id: Some(e.permission.guild_id),
@@ -1479,6 +1631,7 @@ impl Event {
     #[must_use]
     pub fn event_type(&self) -> EventType {
         match self {
+            Self::ActivityJoinRequest(_) => EventType::ActivityJoinRequest,
             Self::ApplicationCommandPermissionsUpdate(_) => {
                 EventType::ApplicationCommandPermissionsUpdate
             },
@@ -1614,6 +1767,7 @@ impl<T> TryFrom<RelatedId<T>> for Option<T> {
 /// Returns [`Error::Json`] if there is an error in deserializing the event data.
 pub fn deserialize_event_with_type(kind: EventType, v: Value) -> Result<Event> {
     Ok(match kind {
+        EventType::ActivityJoinRequest => Event::ActivityJoinRequest(from_value(v)?),
         EventType::ApplicationCommandPermissionsUpdate => {
             Event::ApplicationCommandPermissionsUpdate(from_value(v)?)
         },
@@ -1714,6 +1868,10 @@ pub fn deserialize_event_with_type(kind: EventType, v: Value) -> Result<Event> {
 #[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[non_exhaustive]
 pub enum EventType {
+    /// Indicator that an activity join request payload was received.
+    ///
+    /// This maps to [`ActivityJoinRequestEvent`].
+    ActivityJoinRequest,
     /// Indicator that an application command permission update payload was received.
     ///
     /// This maps to [`ApplicationCommandPermissionsUpdateEvent`].
@@ -2024,6 +2182,7 @@ macro_rules! define_related_ids_for_event_type {
 }
 
 impl EventType {
+    const ACTIVITY_JOIN_REQUEST: &'static str = "ACTIVITY_JOIN_REQUEST";
     const APPLICATION_COMMAND_PERMISSIONS_UPDATE: &'static str =
         "APPLICATION_COMMAND_PERMISSIONS_UPDATE";
     const AUTO_MODERATION_RULE_CREATE: &'static str = "AUTO_MODERATION_RULE_CREATE";
@@ -2092,6 +2251,7 @@ impl EventType {
     #[must_use]
     pub fn name(&self) -> Option<&str> {
         match self {
+            Self::ActivityJoinRequest => Some(Self::ACTIVITY_JOIN_REQUEST),
             Self::ApplicationCommandPermissionsUpdate => {
                 Some(Self::APPLICATION_COMMAND_PERMISSIONS_UPDATE)
             },
@@ -2184,6 +2344,7 @@ impl<'de> Deserialize<'de> for EventType {
                 E: DeError,
             {
                 Ok(match v {
+                    EventType::ACTIVITY_JOIN_REQUEST => EventType::ActivityJoinRequest,
                     EventType::APPLICATION_COMMAND_PERMISSIONS_UPDATE => {
                         EventType::ApplicationCommandPermissionsUpdate
                     },
@@ -2257,3 +2418,100 @@ impl<'de> Deserialize<'de> for EventType {
         deserializer.deserialize_str(EventTypeVisitor)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Event, GatewayEvent, PresenceEvent};
+    use crate::json::{self, json};
+    use crate::model::prelude::*;
+
+    #[test]
+    #[cfg(feature = "model")]
+    fn test_activity_join_invite_payload() {
+        let request: ActivityJoinRequestEvent = json::from_value(json!({
+            "user": {"id": "1"},
+            "application_id": "2",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            request.join_invite_payload("party"),
+            json!({
+                "content": "",
+                "application_id": "2",
+                "activity": {"type": 1, "party_id": "party"},
+            }),
+        );
+    }
+
+    #[test]
+    fn test_presences_replace_dispatch() {
+        let event: GatewayEvent = json::from_value(json!({
+            "op": 0,
+            "s": 3,
+            "t": "PRESENCES_REPLACE",
+            "d": [
+                {
+                    "user": {"id": "1"},
+                    "status": "online",
+                    "activities": [{"type": 0, "name": "Game"}],
+                },
+                {"user": {"id": "2"}, "status": "idle", "activities": []},
+            ],
+        }))
+        .unwrap();
+
+        match event {
+            GatewayEvent::Dispatch(3, Event::PresencesReplace(event)) => {
+                let ids: Vec<_> = event.presences.iter().map(|p| p.user.id).collect();
+                assert_eq!(ids, [UserId(1), UserId(2)]);
+                assert_eq!(event.presences[0].activities[0].name, "Game");
+                assert_eq!(event.presences[1].status, OnlineStatus::Idle);
+            },
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_presence_event_from_update() {
+        let presence = |name, activity| -> PresenceUpdateEvent {
+            json::from_value(json!({
+                "user": {"id": "1", "username": name, "discriminator": "0001", "bot": false},
+                "status": "online",
+                "activities": [{"type": 0, "name": activity}],
+            }))
+            .unwrap()
+        };
+
+        let old = presence("old", "Game");
+        let new = presence("new", "Other game");
+
+        let events = PresenceEvent::from_update(Some(&old.presence), &new);
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], PresenceEvent::Updated(e) if e.presence.user.id == UserId(1)));
+        assert!(matches!(
+            &events[1],
+            PresenceEvent::UserUpdated { old: Some(old), new }
+                if old.name.as_deref() == Some("old") && new.name.as_deref() == Some("new")
+        ));
+        assert!(matches!(
+            &events[2],
+            PresenceEvent::ActivityChanged { old_activities, new_activities, .. }
+                if old_activities[0].name == "Game" && new_activities[0].name == "Other game"
+        ));
+
+        assert_eq!(PresenceEvent::from_update(Some(&new.presence), &new).len(), 1);
+
+        // Without a previous presence, only the sent user data is known to
+        // have changed.
+        let mut new = new;
+        new.is_resume_replay = true;
+        let events = PresenceEvent::from_update(None, &new);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], PresenceEvent::Updated(e) if e.is_resume_replay));
+        assert!(matches!(&events[1], PresenceEvent::UserUpdated {
+            old: None,
+            ..
+        }));
+    }
+}
@@ -1,9 +1,32 @@
 //! Models pertaining to the gateway.
 
+use std::convert::TryFrom;
+use std::error::Error as StdError;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "model")]
+use reqwest::StatusCode;
+#[cfg(feature = "model")]
+use tracing::warn;
 use url::Url;
 
 use super::prelude::*;
+use super::timestamp;
 use super::utils::*;
+#[cfg(all(feature = "model", feature = "builder"))]
+use crate::builder::CreateEmbed;
+use crate::constants::{
+    OpCode,
+    ACTIVITY_ASSET_TEXT_MAX_LENGTH,
+    ACTIVITY_BUTTON_LABEL_MAX_LENGTH,
+    ACTIVITY_BUTTON_MAX_COUNT,
+};
+#[cfg(feature = "model")]
+use crate::http::CacheHttp;
+#[cfg(feature = "utils")]
+use crate::utils::Colour;
 
 /// A representation of the data retrieved from the bot gateway endpoint.
 ///
@@ -26,6 +49,25 @@ pub struct BotGateway {
     pub url: String,
 }
 
+#[cfg(feature = "model")]
+impl BotGateway {
+    /// Whether a session can be started, that is whether an IDENTIFY can be
+    /// sent, right away.
+    #[must_use]
+    pub fn can_identify_now(&self) -> bool {
+        self.session_start_limit.can_start(1)
+    }
+
+    /// Returns how long to wait before a session can be started.
+    ///
+    /// This is zero if [`Self::can_identify_now`], and the time until the
+    /// session start ratelimit period resets otherwise.
+    #[must_use]
+    pub fn will_be_able_to_identify_after(&self) -> Duration {
+        self.session_start_limit.throttle_start(1).unwrap_or_default()
+    }
+}
+
 /// Representation of an activity that a [`User`] is performing.
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway#activity-object-activity-structure).
@@ -40,6 +82,9 @@ pub struct Activity {
     pub details: Option<String>,
     /// Activity flags describing what the payload includes.
     pub flags: Option<ActivityFlags>,
+    /// When the activity was added to the user's session.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "timestamp::millis::option")]
+    pub created_at: Option<Timestamp>,
     /// Whether or not the activity is an instanced game session.
     pub instance: Option<bool>,
     /// The type of activity being performed
@@ -70,12 +115,23 @@ pub struct Activity {
     pub url: Option<Url>,
     /// The buttons of this activity.
     ///
-    /// **Note**: There can only be up to 2 buttons.
+    /// **Note**: There can only be up to 2 buttons. Use [`Self::with_buttons`]
+    /// to set them with this limit checked.
     #[serde(default, deserialize_with = "deserialize_buttons")]
     pub buttons: Vec<ActivityButton>,
+    /// The fields sent by Discord that are not modelled by this struct, such
+    /// as newly added or undocumented ones.
+    ///
+    /// These are kept as is when serializing the activity, but are never sent
+    /// to the gateway in a presence update. Keys of modelled fields must not
+    /// be inserted, as they would be serialized twice.
+    ///
+    /// Requires the `presence_extra_fields` feature.
+    #[cfg(feature = "presence_extra_fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
-#[cfg(feature = "model")]
 impl Activity {
     /// Common constructor for the different `ActivityType`s.
     fn new(name: String, kind: ActivityType) -> Self {
@@ -84,6 +140,7 @@ impl Activity {
             assets: None,
             details: None,
             flags: None,
+            created_at: None,
             instance: None,
             kind,
             name,
@@ -98,9 +155,28 @@ impl Activity {
             session_id: None,
             url: None,
             buttons: vec![],
+            #[cfg(feature = "presence_extra_fields")]
+            extra: HashMap::new(),
         }
     }
 
+    /// Parses an activity from its raw JSON, as found in the `activities` of
+    /// presence updates sent by Discord.
+    ///
+    /// This is useful when intercepting raw gateway payloads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Json`] if the JSON is not a valid activity.
+    ///
+    /// [`Error::Json`]: crate::Error::Json
+    pub fn from_discord_activity_update(json: &Value) -> Result<Self> {
+        crate::json::from_value(json.clone())
+    }
+}
+
+#[cfg(feature = "model")]
+impl Activity {
     /// Creates a [`Activity`] struct that appears as a `Playing <name>` status.
     ///
     /// **Note**: Maximum `name` length is 128.
@@ -267,6 +343,478 @@ impl Activity {
     {
         Activity::new(name.to_string(), ActivityType::Competing)
     }
+
+    /// Returns the activity's name, truncated to at most `max_len` characters.
+    ///
+    /// If the name was truncated, `…` is appended to it. Truncation happens on
+    /// character boundaries, so multi-byte characters are never split.
+    #[must_use]
+    pub fn name_truncated(&self, max_len: usize) -> String {
+        truncate(&self.name, max_len)
+    }
+
+    /// Returns the activity's details, truncated to at most `max_len`
+    /// characters.
+    ///
+    /// Refer to [`Self::name_truncated`] for how the truncation is done.
+    #[must_use]
+    pub fn details_truncated(&self, max_len: usize) -> Option<String> {
+        self.details.as_deref().map(|details| truncate(details, max_len))
+    }
+
+    /// Returns the activity's state, truncated to at most `max_len` characters.
+    ///
+    /// Refer to [`Self::name_truncated`] for how the truncation is done.
+    #[must_use]
+    pub fn state_truncated(&self, max_len: usize) -> Option<String> {
+        self.state.as_deref().map(|state| truncate(state, max_len))
+    }
+
+    /// Checks whether the activity's flags are consistent with its type.
+    ///
+    /// [`ActivityFlags::SYNC`] is only used with [`Listening`] activities,
+    /// [`ActivityFlags::EMBEDDED`] only with [`Playing`] activities, and
+    /// custom statuses have no flags at all.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ActivityError::InvalidFlags`] with the offending flags if any
+    /// of them are not used with the activity's type.
+    ///
+    /// [`Listening`]: ActivityType::Listening
+    /// [`Playing`]: ActivityType::Playing
+    pub fn validate_flags(&self) -> StdResult<(), ActivityError> {
+        let flags = self.flags.unwrap_or_default() - ActivityFlags::allowed_for(self.kind);
+
+        if flags.is_empty() {
+            Ok(())
+        } else {
+            Err(ActivityError::InvalidFlags {
+                kind: self.kind,
+                flags,
+            })
+        }
+    }
+
+    /// Sets the details of the activity, shown below its name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serenity::model::gateway::Activity;
+    ///
+    /// let activity = Activity::competing("Arena").with_details("Round 2").with_state("Team A vs B");
+    /// ```
+    #[must_use]
+    pub fn with_details(mut self, details: impl ToString) -> Self {
+        self.details = Some(details.to_string());
+
+        self
+    }
+
+    /// Sets the state of the activity, shown below its details.
+    ///
+    /// For [`Custom`] activities, this is the text of the custom status.
+    ///
+    /// [`Custom`]: ActivityType::Custom
+    #[must_use]
+    pub fn with_state(mut self, state: impl ToString) -> Self {
+        self.state = Some(state.to_string());
+
+        self
+    }
+
+    /// Sets the emoji of a [`Custom`] status.
+    ///
+    /// Only custom statuses have an emoji, so for other activity types this
+    /// logs a warning and returns the activity unchanged.
+    ///
+    /// [`Custom`]: ActivityType::Custom
+    #[must_use]
+    pub fn with_emoji(mut self, emoji: ActivityEmoji) -> Self {
+        if self.kind == ActivityType::Custom {
+            self.emoji = Some(emoji);
+        } else {
+            warn!("Ignoring emoji set on a {:?} activity, which is not a custom status", self.kind);
+        }
+
+        self
+    }
+
+    /// Sets a Unicode emoji, such as `"\u{1F3AE}"`, as the emoji of a
+    /// [`Custom`] status.
+    ///
+    /// Refer to [`Self::with_emoji`] for more information.
+    ///
+    /// [`Custom`]: ActivityType::Custom
+    #[must_use]
+    pub fn with_emoji_unicode(self, emoji: impl ToString) -> Self {
+        self.with_emoji(ActivityEmoji {
+            name: emoji.to_string(),
+            id: None,
+            animated: None,
+        })
+    }
+
+    /// Sets the buttons of the activity.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ActivityError::TooManyButtons`] if more than 2 buttons are
+    /// given, or [`ActivityError::ButtonLabelTooLong`] if a label is longer
+    /// than 32 characters, as Discord would reject the activity.
+    pub fn with_buttons(mut self, buttons: Vec<ActivityButton>) -> StdResult<Self, ActivityError> {
+        self.buttons = buttons;
+        self.validate_buttons()?;
+
+        Ok(self)
+    }
+
+    /// Checks that the activity has no more than 2 buttons, with labels of
+    /// at most 32 characters.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ActivityError::TooManyButtons`] with the number of buttons if
+    /// there are more than 2, or [`ActivityError::ButtonLabelTooLong`] with
+    /// the length of the first label that is too long.
+    pub fn validate_buttons(&self) -> StdResult<(), ActivityError> {
+        if self.buttons.len() > ACTIVITY_BUTTON_MAX_COUNT {
+            return Err(ActivityError::TooManyButtons(self.buttons.len()));
+        }
+
+        match self
+            .buttons
+            .iter()
+            .map(|button| button.label.chars().count())
+            .find(|&length| length > ACTIVITY_BUTTON_LABEL_MAX_LENGTH)
+        {
+            Some(length) => Err(ActivityError::ButtonLabelTooLong(length)),
+            None => Ok(()),
+        }
+    }
+
+    /// Truncates the button labels longer than 32 characters, as sent by some
+    /// custom clients, so that the activity passes [`Self::validate_buttons`]
+    /// when re-broadcast. Truncated labels end with an ellipsis.
+    pub fn truncate_button_labels(&mut self) {
+        for button in &mut self.buttons {
+            if button.label.chars().count() > ACTIVITY_BUTTON_LABEL_MAX_LENGTH {
+                // Leaves room for the ellipsis.
+                button.label = truncate(&button.label, ACTIVITY_BUTTON_LABEL_MAX_LENGTH - 1);
+            }
+        }
+    }
+
+    /// Whether the activity has a party that can be joined.
+    ///
+    /// Refer to [`ActivityParty::is_joinable`] for more information.
+    #[must_use]
+    pub fn is_joinable(&self) -> bool {
+        matches!(&self.party, Some(party) if party.is_joinable(self.flags.unwrap_or_default()))
+    }
+
+    /// Detects the service a [`Listening`] activity is being played from.
+    ///
+    /// Spotify's own presence is recognised by its name and by the absence of
+    /// an application, as well as by its `spotify:` asset prefix. Rich
+    /// presence applications merely named "Spotify" are reported as
+    /// [`MusicSource::Other`].
+    ///
+    /// Returns `None` if this is not a [`Listening`] activity.
+    ///
+    /// [`Listening`]: ActivityType::Listening
+    #[must_use]
+    pub fn music_source(&self) -> Option<MusicSource> {
+        if self.kind != ActivityType::Listening {
+            return None;
+        }
+
+        let large_image = self.assets.as_ref().and_then(|a| a.large_image.as_deref());
+
+        Some(match self.name.as_str() {
+            "Spotify" if self.application_id.is_none() => MusicSource::Spotify,
+            _ if matches!(large_image, Some(i) if i.starts_with("spotify:")) => {
+                MusicSource::Spotify
+            },
+            "YouTube Music" => MusicSource::YouTubeMusic,
+            "Apple Music" => MusicSource::AppleMusic,
+            _ => MusicSource::Other,
+        })
+    }
+
+    /// Detects the service a [`Streaming`] activity is being streamed to,
+    /// from the host of its [`url`].
+    ///
+    /// Returns `None` if this is not a [`Streaming`] activity, or if it has
+    /// no URL.
+    ///
+    /// [`Streaming`]: ActivityType::Streaming
+    /// [`url`]: Self::url
+    #[must_use]
+    pub fn streaming_platform(&self) -> Option<StreamingPlatform> {
+        if self.kind != ActivityType::Streaming {
+            return None;
+        }
+
+        let host = self.url.as_ref()?.host_str()?;
+        // Matches the domain itself and its subdomains.
+        let is_domain = |domain: &str| match host.strip_suffix(domain) {
+            Some(sub) => sub.is_empty() || sub.ends_with('.'),
+            None => false,
+        };
+
+        Some(if is_domain("twitch.tv") {
+            StreamingPlatform::Twitch
+        } else if is_domain("youtube.com") || is_domain("youtu.be") {
+            StreamingPlatform::YouTube
+        } else if is_domain("facebook.com") || is_domain("fb.gg") {
+            StreamingPlatform::Facebook
+        } else {
+            StreamingPlatform::Other(host.to_string())
+        })
+    }
+
+    /// Whether the activity matches all the conditions set on the filter.
+    ///
+    /// A filter without any conditions matches every activity.
+    #[must_use]
+    pub fn matches_filter(&self, filter: &ActivityFilter) -> bool {
+        if matches!(filter.kind, Some(kind) if kind != self.kind) {
+            return false;
+        }
+
+        if filter.requires_streaming && self.kind != ActivityType::Streaming {
+            return false;
+        }
+
+        if filter.application_id.is_some() && filter.application_id != self.application_id {
+            return false;
+        }
+
+        if matches!(&filter.name_contains, Some(name) if !self.name.contains(name.as_str())) {
+            return false;
+        }
+
+        match &filter.name_contains_ci {
+            Some(name) => self.name.to_lowercase().contains(&name.to_lowercase()),
+            None => true,
+        }
+    }
+
+    /// Applies a partial update to the activity, keeping the fields the update
+    /// does not carry.
+    ///
+    /// The fields are merged as follows:
+    /// - [`Self::name`] and [`Self::kind`] are replaced only if the update's
+    ///   name is not empty, as a missing type is indistinguishable from
+    ///   [`ActivityType::Playing`];
+    /// - [`Self::buttons`] are replaced only if the update has any, so an empty
+    ///   list does not clear the existing buttons;
+    /// - every other field is replaced only if the update's value is `Some`,
+    ///   so a field can not be cleared through a merge.
+    ///
+    /// This keeps, for example, the timestamps and assets of a song stable
+    /// when only its details line changes.
+    pub fn merge(&mut self, update: Activity) {
+        fn merge_field<T>(field: &mut Option<T>, update: Option<T>) {
+            if update.is_some() {
+                *field = update;
+            }
+        }
+
+        if !update.name.is_empty() {
+            self.name = update.name;
+            self.kind = update.kind;
+        }
+        if !update.buttons.is_empty() {
+            self.buttons = update.buttons;
+        }
+
+        merge_field(&mut self.application_id, update.application_id);
+        merge_field(&mut self.assets, update.assets);
+        merge_field(&mut self.details, update.details);
+        merge_field(&mut self.flags, update.flags);
+        merge_field(&mut self.instance, update.instance);
+        merge_field(&mut self.party, update.party);
+        merge_field(&mut self.secrets, update.secrets);
+        merge_field(&mut self.state, update.state);
+        merge_field(&mut self.emoji, update.emoji);
+        merge_field(&mut self.timestamps, update.timestamps);
+        #[cfg(feature = "unstable_discord_api")]
+        merge_field(&mut self.sync_id, update.sync_id);
+        #[cfg(feature = "unstable_discord_api")]
+        merge_field(&mut self.session_id, update.session_id);
+        merge_field(&mut self.url, update.url);
+    }
+
+    /// Returns embed fields, as `(name, value, inline)` tuples, describing the
+    /// activity's rich presence data.
+    ///
+    /// Fields are included for the details, the state, the party size as
+    /// `current/max`, the time elapsed since the activity started, and the
+    /// stream URL, each only if present. The fields can be passed to
+    /// [`CreateEmbed::fields`] as is.
+    ///
+    /// [`CreateEmbed::fields`]: crate::builder::CreateEmbed::fields
+    #[must_use]
+    pub fn to_rich_embed_fields(&self) -> Vec<(String, String, bool)> {
+        let now = SystemTime::UNIX_EPOCH.elapsed().unwrap_or_default().as_millis() as u64;
+
+        self.rich_embed_fields_at(now)
+    }
+
+    fn rich_embed_fields_at(&self, now: u64) -> Vec<(String, String, bool)> {
+        let mut fields = Vec::new();
+
+        if let Some(details) = &self.details {
+            fields.push(("Details".to_string(), details.clone(), false));
+        }
+        if let Some(state) = &self.state {
+            fields.push(("State".to_string(), state.clone(), false));
+        }
+        if let Some([current, max]) = self.party.as_ref().and_then(|p| p.size) {
+            fields.push(("Party".to_string(), format!("{}/{}", current, max), true));
+        }
+        if let Some(start) = self.timestamps.as_ref().and_then(|t| t.start) {
+            let secs = now.saturating_sub(start) / 1000;
+            let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+            let elapsed = if hours > 0 {
+                format!("{}:{:02}:{:02}", hours, minutes, secs)
+            } else {
+                format!("{:02}:{:02}", minutes, secs)
+            };
+
+            fields.push(("Elapsed".to_string(), elapsed, true));
+        }
+        if let Some(url) = &self.url {
+            fields.push(("Stream".to_string(), url.to_string(), false));
+        }
+
+        fields
+    }
+
+    /// Returns the time elapsed since the activity started, such as `1h 2m 3s`.
+    ///
+    /// Zero components are omitted, so that 65 seconds are displayed as
+    /// `1m 5s`. A start in the future is displayed as `0s`.
+    ///
+    /// Returns [`None`] if the activity has no start timestamp.
+    #[must_use]
+    pub fn elapsed_time_display(&self) -> Option<String> {
+        let now = SystemTime::UNIX_EPOCH.elapsed().unwrap_or_default().as_millis() as u64;
+
+        self.elapsed_time_display_at(now)
+    }
+
+    fn elapsed_time_display_at(&self, now: u64) -> Option<String> {
+        let start = self.timestamps.as_ref()?.start?;
+        let secs = now.saturating_sub(start) / 1000;
+
+        let components = [(secs / 3600, 'h'), (secs / 60 % 60, 'm'), (secs % 60, 's')];
+        let display = components
+            .iter()
+            .filter(|(value, _)| *value > 0)
+            .map(|(value, unit)| format!("{}{}", value, unit))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Some(if display.is_empty() { "0s".to_string() } else { display })
+    }
+}
+
+/// A set of conditions an activity can be checked against, with
+/// [`Activity::matches_filter`].
+///
+/// Conditions that are not set are ignored.
+///
+/// # Examples
+///
+/// Matching activities of users streaming a game:
+///
+/// ```rust
+/// use serenity::model::gateway::{Activity, ActivityFilter};
+///
+/// let filter = ActivityFilter::new().requires_streaming(true).name_contains_ci("game");
+///
+/// assert!(Activity::streaming("Some Game", "https://twitch.tv/someone").matches_filter(&filter));
+/// assert!(!Activity::playing("Some Game").matches_filter(&filter));
+/// ```
+#[cfg(feature = "model")]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ActivityFilter {
+    /// The kind the activity must be of.
+    pub kind: Option<ActivityType>,
+    /// A string the activity's name must contain.
+    pub name_contains: Option<String>,
+    /// A string the activity's name must contain, ignoring case.
+    pub name_contains_ci: Option<String>,
+    /// The application the activity must belong to.
+    pub application_id: Option<ApplicationId>,
+    /// Whether the activity must be a [`Streaming`] activity.
+    ///
+    /// [`Streaming`]: ActivityType::Streaming
+    pub requires_streaming: bool,
+}
+
+#[cfg(feature = "model")]
+impl ActivityFilter {
+    /// Creates a filter without any conditions, matching every activity.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the kind the activity must be of.
+    #[must_use]
+    pub fn kind(mut self, kind: ActivityType) -> Self {
+        self.kind = Some(kind);
+
+        self
+    }
+
+    /// Sets a string the activity's name must contain.
+    #[must_use]
+    pub fn name_contains(mut self, name: impl Into<String>) -> Self {
+        self.name_contains = Some(name.into());
+
+        self
+    }
+
+    /// Sets a string the activity's name must contain, ignoring case.
+    #[must_use]
+    pub fn name_contains_ci(mut self, name: impl Into<String>) -> Self {
+        self.name_contains_ci = Some(name.into());
+
+        self
+    }
+
+    /// Sets the application the activity must belong to.
+    #[must_use]
+    pub fn application_id(mut self, application_id: impl Into<ApplicationId>) -> Self {
+        self.application_id = Some(application_id.into());
+
+        self
+    }
+
+    /// Sets whether the activity must be a [`Streaming`] activity.
+    ///
+    /// [`Streaming`]: ActivityType::Streaming
+    #[must_use]
+    pub fn requires_streaming(mut self, requires_streaming: bool) -> Self {
+        self.requires_streaming = requires_streaming;
+
+        self
+    }
+}
+
+fn truncate(s: &str, max_len: usize) -> String {
+    match s.char_indices().nth(max_len) {
+        Some((idx, _)) => format!("{}\u{2026}", &s[..idx]),
+        None => s.to_string(),
+    }
 }
 
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway#activity-object-activity-buttons).
@@ -274,6 +822,10 @@ impl Activity {
 #[non_exhaustive]
 pub struct ActivityButton {
     /// The text shown on the button.
+    ///
+    /// Labels longer than 32 characters, as sent by some custom clients, are
+    /// kept as is when deserializing. Refer to
+    /// [`Activity::truncate_button_labels`] to make them valid.
     pub label: String,
     /// The url opened when clicking the button.
     ///
@@ -282,6 +834,35 @@ pub struct ActivityButton {
     pub url: String,
 }
 
+#[cfg(feature = "model")]
+impl ActivityButton {
+    /// Creates a button with the given label, opening the given URL.
+    #[must_use]
+    pub fn new(label: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            url: url.into(),
+        }
+    }
+}
+
+#[cfg(feature = "development")]
+impl ActivityButton {
+    /// Opens the button's URL in the default browser of the machine the bot
+    /// is running on, to check that it links to the right place.
+    ///
+    /// **Warning**: This is only meant for testing on a development machine.
+    /// Do not call this from an event handler in production, as it launches a
+    /// program on the host and blocks until the launcher exits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no program could be launched to open the URL.
+    pub fn open_url(&self) -> std::io::Result<()> {
+        open::that(self.url.as_str())
+    }
+}
+
 /// The assets for an activity.
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway#activity-object-activity-assets).
@@ -298,36 +879,265 @@ pub struct ActivityAssets {
     pub small_text: Option<String>,
 }
 
-bitflags! {
-    /// A set of flags defining what is in an activity's payload.
+#[cfg(feature = "model")]
+impl ActivityAssets {
+    /// Creates a set of assets using an external image as the large image.
     ///
-    /// [Discord docs](https://discord.com/developers/docs/topics/gateway#activity-object-activity-flags).
-    #[derive(Default)]
-    pub struct ActivityFlags: u64 {
-        /// Whether the activity is an instance activity.
-        const INSTANCE = 1 << 0;
-        /// Whether the activity is joinable.
-        const JOIN = 1 << 1;
-        /// Whether the activity can be spectated.
-        const SPECTATE = 1 << 2;
-        /// Whether a request can be sent to join the user's party.
-        const JOIN_REQUEST = 1 << 3;
-        /// Whether the activity can be synced.
-        const SYNC = 1 << 4;
-        /// Whether the activity can be played.
-        const PLAY = 1 << 5;
-        /// Whether the activity party is friend only.
-        const PARTY_PRIVACY_FRIENDS = 1 << 6;
-        /// Whether the activity party is in a voice channel.
-        const PARTY_PRIVACY_VOICE_CHANNEL = 1 << 7;
-        /// Whether the activity can be embedded.
-        const EMBEDDED = 1 << 8;
+    /// The URL is converted to the `mp:external/...` form used by Discord's
+    /// media proxy, which is how user accounts reference images not uploaded
+    /// as application assets.
+    ///
+    /// **Note**: Discord may require the path to be signed, in which case the
+    /// value returned by the external assets endpoint should be used instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ActivityError::InvalidUrl`] if `url` is not a valid http(s)
+    /// URL.
+    pub fn external_image(url: &str) -> StdResult<Self, ActivityError> {
+        let url = Url::parse(url).map_err(|_| ActivityError::InvalidUrl)?;
+
+        if !matches!(url.scheme(), "http" | "https") || !url.has_host() {
+            return Err(ActivityError::InvalidUrl);
+        }
+
+        let path = &url[url::Position::BeforeHost..];
+
+        Ok(Self {
+            large_image: Some(format!("mp:external/{}/{}", url.scheme(), path)),
+            large_text: None,
+            small_image: None,
+            small_text: None,
+        })
+    }
+
+    /// Creates a builder for a set of assets, checking the length of the
+    /// hover texts.
+    pub fn builder() -> ActivityAssetsBuilder {
+        ActivityAssetsBuilder::default()
+    }
+
+    /// Returns the raw keys of the large and small images, in that order.
+    ///
+    /// These identify an image independently of the activity, so they can be
+    /// used as cache keys before being resolved to full URLs.
+    #[inline]
+    #[must_use]
+    pub fn image_keys(&self) -> (Option<&str>, Option<&str>) {
+        (self.large_image.as_deref(), self.small_image.as_deref())
     }
 }
 
-/// Information about an activity's party.
+/// A builder for [`ActivityAssets`], created with [`ActivityAssets::builder`].
 ///
-/// [Discord docs](https://discord.com/developers/docs/topics/gateway#activity-object-activity-party).
+/// # Examples
+///
+/// ```rust
+/// use serenity::model::gateway::ActivityAssets;
+///
+/// let assets = ActivityAssets::builder()
+///     .large_image("mp:external/https/example.com/cover.png")
+///     .large_text("Album title")
+///     .build()
+///     .expect("Hover text too long");
+/// ```
+#[cfg(feature = "model")]
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct ActivityAssetsBuilder {
+    large_image: Option<String>,
+    large_text: Option<String>,
+    small_image: Option<String>,
+    small_text: Option<String>,
+}
+
+#[cfg(feature = "model")]
+impl ActivityAssetsBuilder {
+    /// Sets the key of the large image, such as an application asset Id or
+    /// an `mp:external/...` path.
+    pub fn large_image(mut self, key: impl ToString) -> Self {
+        self.large_image = Some(key.to_string());
+
+        self
+    }
+
+    /// Sets the text displayed when hovering over the large image.
+    ///
+    /// **Note**: Must be at most 128 characters long.
+    pub fn large_text(mut self, text: impl ToString) -> Self {
+        self.large_text = Some(text.to_string());
+
+        self
+    }
+
+    /// Sets the key of the small image.
+    pub fn small_image(mut self, key: impl ToString) -> Self {
+        self.small_image = Some(key.to_string());
+
+        self
+    }
+
+    /// Sets the text displayed when hovering over the small image.
+    ///
+    /// **Note**: Must be at most 128 characters long.
+    pub fn small_text(mut self, text: impl ToString) -> Self {
+        self.small_text = Some(text.to_string());
+
+        self
+    }
+
+    /// Builds the assets.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ActivityError::AssetTextTooLong`] with the length of the
+    /// offending text if a hover text is longer than 128 characters.
+    pub fn build(self) -> StdResult<ActivityAssets, ActivityError> {
+        for text in self.large_text.iter().chain(&self.small_text) {
+            let length = text.chars().count();
+
+            if length > ACTIVITY_ASSET_TEXT_MAX_LENGTH {
+                return Err(ActivityError::AssetTextTooLong(length));
+            }
+        }
+
+        Ok(ActivityAssets {
+            large_image: self.large_image,
+            large_text: self.large_text,
+            small_image: self.small_image,
+            small_text: self.small_text,
+        })
+    }
+}
+
+bitflags! {
+    /// A set of flags defining what is in an activity's payload.
+    ///
+    /// [Discord docs](https://discord.com/developers/docs/topics/gateway#activity-object-activity-flags).
+    #[derive(Default)]
+    pub struct ActivityFlags: u64 {
+        /// Whether the activity is an instance activity.
+        const INSTANCE = 1 << 0;
+        /// Whether the activity is joinable.
+        const JOIN = 1 << 1;
+        /// Whether the activity can be spectated.
+        const SPECTATE = 1 << 2;
+        /// Whether a request can be sent to join the user's party.
+        const JOIN_REQUEST = 1 << 3;
+        /// Whether the activity can be synced.
+        const SYNC = 1 << 4;
+        /// Whether the activity can be played.
+        const PLAY = 1 << 5;
+        /// Whether the activity party is friend only.
+        const PARTY_PRIVACY_FRIENDS = 1 << 6;
+        /// Whether the activity party is in a voice channel.
+        const PARTY_PRIVACY_VOICE_CHANNEL = 1 << 7;
+        /// Whether the activity can be embedded.
+        const EMBEDDED = 1 << 8;
+    }
+}
+
+#[cfg(feature = "model")]
+impl ActivityFlags {
+    /// Returns the flags conventionally set on activities of the given type.
+    ///
+    /// Games are playable, and listening activities such as Spotify's can
+    /// additionally be synced with. Other types have no flags by default.
+    #[must_use]
+    pub fn default_for(kind: ActivityType) -> ActivityFlags {
+        match kind {
+            ActivityType::Playing => ActivityFlags::PLAY,
+            ActivityType::Listening => ActivityFlags::SYNC | ActivityFlags::PLAY,
+            _ => ActivityFlags::empty(),
+        }
+    }
+
+    /// Returns the flags that may be set on activities of the given type.
+    fn allowed_for(kind: ActivityType) -> ActivityFlags {
+        match kind {
+            // Custom statuses only carry a state and an emoji.
+            ActivityType::Custom => ActivityFlags::empty(),
+            // Embedded activities are launched as games.
+            ActivityType::Playing => ActivityFlags::all() - ActivityFlags::SYNC,
+            ActivityType::Listening => ActivityFlags::all() - ActivityFlags::EMBEDDED,
+            _ => ActivityFlags::all() - ActivityFlags::SYNC - ActivityFlags::EMBEDDED,
+        }
+    }
+}
+
+/// Alternative serialized forms of [`ActivityFlags`].
+pub mod activity_flags {
+    /// Used with `#[serde(with = "activity_flags::names")]`
+    ///
+    /// Serializes [`ActivityFlags`] as an alphabetically sorted array of flag
+    /// names, such as `["PLAY", "SYNC"]`, rather than as a bitmask. This keeps
+    /// stored activity data readable and diffable.
+    ///
+    /// Bits without a named flag are not serialized.
+    ///
+    /// [`ActivityFlags`]: super::ActivityFlags
+    pub mod names {
+        use serde::de::{Error, Unexpected};
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        use crate::model::gateway::ActivityFlags;
+
+        // Sorted by name.
+        const NAMES: [(&str, ActivityFlags); 9] = [
+            ("EMBEDDED", ActivityFlags::EMBEDDED),
+            ("INSTANCE", ActivityFlags::INSTANCE),
+            ("JOIN", ActivityFlags::JOIN),
+            ("JOIN_REQUEST", ActivityFlags::JOIN_REQUEST),
+            ("PARTY_PRIVACY_FRIENDS", ActivityFlags::PARTY_PRIVACY_FRIENDS),
+            ("PARTY_PRIVACY_VOICE_CHANNEL", ActivityFlags::PARTY_PRIVACY_VOICE_CHANNEL),
+            ("PLAY", ActivityFlags::PLAY),
+            ("SPECTATE", ActivityFlags::SPECTATE),
+            ("SYNC", ActivityFlags::SYNC),
+        ];
+
+        /// Deserializes flags from an array of flag names.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the value is not an array of strings, or if one
+        /// of them is not the name of a flag.
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<ActivityFlags, D::Error> {
+            let names = Vec::<String>::deserialize(deserializer)?;
+            let mut flags = ActivityFlags::empty();
+
+            for name in names {
+                let (_, flag) = NAMES.iter().find(|(n, _)| *n == name).ok_or_else(|| {
+                    Error::invalid_value(Unexpected::Str(&name), &"an activity flag name")
+                })?;
+
+                flags |= *flag;
+            }
+
+            Ok(flags)
+        }
+
+        /// Serializes flags as a sorted array of flag names.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the serializer fails.
+        pub fn serialize<S: Serializer>(
+            flags: &ActivityFlags,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            let names: Vec<&str> =
+                NAMES.iter().filter(|(_, flag)| flags.contains(*flag)).map(|(n, _)| *n).collect();
+
+            names.serialize(serializer)
+        }
+    }
+}
+
+/// Information about an activity's party.
+///
+/// [Discord docs](https://discord.com/developers/docs/topics/gateway#activity-object-activity-party).
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[non_exhaustive]
 pub struct ActivityParty {
@@ -337,6 +1147,37 @@ pub struct ActivityParty {
     pub size: Option<[u64; 2]>,
 }
 
+#[cfg(feature = "model")]
+impl ActivityParty {
+    /// Whether the party has reached its maximum size.
+    ///
+    /// Returns `None` if the party's size is unknown.
+    #[must_use]
+    pub fn is_full(&self) -> Option<bool> {
+        self.size.map(|[current, max]| current >= max)
+    }
+
+    /// The number of members that can still join the party.
+    ///
+    /// This is `0` if the party is full, including when its reported current
+    /// size is above its maximum. Returns `None` if the party's size is
+    /// unknown.
+    #[must_use]
+    pub fn free_slots(&self) -> Option<u64> {
+        self.size.map(|[current, max]| max.saturating_sub(current))
+    }
+
+    /// Whether the party can be joined, given the flags of its activity.
+    ///
+    /// This is the case if the activity has the [`ActivityFlags::JOIN`] flag
+    /// and the party is not full. A party of unknown size is assumed not to
+    /// be full.
+    #[must_use]
+    pub fn is_joinable(&self, flags: ActivityFlags) -> bool {
+        flags.contains(ActivityFlags::JOIN) && self.is_full() != Some(true)
+    }
+}
+
 /// Secrets for an activity.
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway#activity-object-activity-secrets).
@@ -352,6 +1193,94 @@ pub struct ActivitySecrets {
     pub spectate: Option<String>,
 }
 
+#[cfg(feature = "model")]
+impl ActivitySecrets {
+    /// Generates a set of secrets with random `join`, `match` and `spectate`
+    /// values.
+    ///
+    /// Each secret is made of 16 bytes taken from the operating system's
+    /// random number generator, encoded as unpadded URL-safe base64.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operating system's random number generator fails.
+    #[must_use]
+    pub fn generate() -> Self {
+        Self {
+            join: Some(random_secret()),
+            match_: Some(random_secret()),
+            spectate: Some(random_secret()),
+        }
+    }
+
+    /// Generates a set of secrets where only the `join` secret is present.
+    ///
+    /// Refer to [`Self::generate`] for how the secret is produced.
+    #[must_use]
+    pub fn generate_join_only() -> Self {
+        Self {
+            join: Some(random_secret()),
+            match_: None,
+            spectate: None,
+        }
+    }
+
+    /// Generates a set of secrets where only the `spectate` secret is present.
+    ///
+    /// Refer to [`Self::generate`] for how the secret is produced.
+    #[must_use]
+    pub fn generate_spectate_only() -> Self {
+        Self {
+            join: None,
+            match_: None,
+            spectate: Some(random_secret()),
+        }
+    }
+
+    /// Whether all secrets that are present only contain URL-safe characters:
+    /// ASCII letters and digits, `-`, `_` and `.`.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.secrets().all(|secret| secret.chars().all(is_secret_char))
+    }
+
+    /// Returns a copy of the secrets where characters that are not URL-safe
+    /// are replaced with `_`.
+    ///
+    /// Refer to [`Self::is_valid`] for the allowed characters.
+    #[must_use]
+    pub fn sanitize(&self) -> ActivitySecrets {
+        let sanitize = |secret: &Option<String>| {
+            secret.as_ref().map(|secret| {
+                secret.chars().map(|c| if is_secret_char(c) { c } else { '_' }).collect()
+            })
+        };
+
+        Self {
+            join: sanitize(&self.join),
+            match_: sanitize(&self.match_),
+            spectate: sanitize(&self.spectate),
+        }
+    }
+
+    fn secrets(&self) -> impl Iterator<Item = &String> {
+        self.join.iter().chain(&self.match_).chain(&self.spectate)
+    }
+}
+
+#[cfg(feature = "model")]
+fn is_secret_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')
+}
+
+#[cfg(feature = "model")]
+fn random_secret() -> String {
+    let mut bytes = [0; 16];
+    getrandom::getrandom(&mut bytes).expect("Failed to get random bytes from the OS");
+
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
 /// Representation of an emoji used in a custom status
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway#activity-object-activity-emoji).
@@ -365,8 +1294,163 @@ pub struct ActivityEmoji {
     pub animated: Option<bool>,
 }
 
+#[cfg(feature = "model")]
+impl ActivityEmoji {
+    /// Generates a URL to the emoji's image, with the given size.
+    ///
+    /// Returns `Ok(None)` for Unicode emojis, as they have no image hosted on
+    /// Discord's CDN.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ActivityError::InvalidImageSize`] if `size` is not a power of
+    /// 2 between 16 and 4096.
+    pub fn url_for_size(&self, size: u32) -> StdResult<Option<Url>, ActivityError> {
+        if !(16..=4096).contains(&size) || !size.is_power_of_two() {
+            return Err(ActivityError::InvalidImageSize);
+        }
+
+        let ext = if self.animated == Some(true) { "gif" } else { "png" };
+
+        Ok(self.id.and_then(|id| Url::parse(&cdn!("/emojis/{}.{}?size={}", id, ext, size)).ok()))
+    }
+
+    /// Generates a URL to the animated version of the emoji's image.
+    ///
+    /// Returns `None` if the emoji is a Unicode emoji or is not animated.
+    #[must_use]
+    pub fn animated_url(&self) -> Option<Url> {
+        if self.animated != Some(true) {
+            return None;
+        }
+
+        Url::parse(&cdn!("/emojis/{}.gif", self.id?)).ok()
+    }
+
+    /// Whether both emojis are the same, ignoring whether they are animated.
+    ///
+    /// Custom emojis are compared by their name and Id, and Unicode emojis,
+    /// which have no Id, by their name.
+    #[must_use]
+    pub fn same_emoji(&self, other: &ActivityEmoji) -> bool {
+        self.id == other.id && self.name == other.name
+    }
+
+    /// Formats the emoji so that Discord clients render it in a message.
+    ///
+    /// Custom emojis are formatted as `<:NAME:EMOJI_ID>`, or
+    /// `<a:NAME:EMOJI_ID>` if animated, while Unicode emojis are returned as
+    /// is, since their name is the emoji itself.
+    ///
+    /// Returns `None` if the emoji has no name, which can not be rendered.
+    #[must_use]
+    pub fn to_mention(&self) -> Option<String> {
+        if self.name.is_empty() {
+            return None;
+        }
+
+        Some(match self.id {
+            Some(id) if self.animated == Some(true) => format!("<a:{}:{}>", self.name, id),
+            Some(id) => format!("<:{}:{}>", self.name, id),
+            None => self.name.clone(),
+        })
+    }
+}
+
+/// An error returned when building or validating activity data.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ActivityError {
+    /// The requested image size is not a power of 2 between 16 and 4096.
+    InvalidImageSize,
+    /// The provided URL is not a valid http(s) URL.
+    InvalidUrl,
+    /// The activity has flags that are not used with its type.
+    InvalidFlags {
+        /// The type of the activity.
+        kind: ActivityType,
+        /// The flags that are not used with the activity's type.
+        flags: ActivityFlags,
+    },
+    /// The activity has more than 2 buttons. Contains the number of buttons.
+    TooManyButtons(usize),
+    /// The label of a button is longer than 32 characters. Contains the
+    /// length of the label.
+    ButtonLabelTooLong(usize),
+    /// A hover text of the activity's assets is longer than 128 characters.
+    /// Contains the length of the text.
+    AssetTextTooLong(usize),
+}
+
+impl fmt::Display for ActivityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidImageSize => f.write_str("Image size must be a power of 2 in 16..=4096."),
+            Self::InvalidUrl => f.write_str("Invalid http(s) URL."),
+            Self::InvalidFlags {
+                kind,
+                flags,
+            } => write!(f, "Activity flags {:?} are not used with {:?} activities.", flags, kind),
+            Self::TooManyButtons(count) => write!(
+                f,
+                "Activities can have at most {} buttons, got {}.",
+                ACTIVITY_BUTTON_MAX_COUNT, count
+            ),
+            Self::ButtonLabelTooLong(length) => write!(
+                f,
+                "Button labels can be at most {} characters long, got {}.",
+                ACTIVITY_BUTTON_LABEL_MAX_LENGTH, length
+            ),
+            Self::AssetTextTooLong(length) => write!(
+                f,
+                "Asset hover texts can be at most {} characters long, got {}.",
+                ACTIVITY_ASSET_TEXT_MAX_LENGTH, length
+            ),
+        }
+    }
+}
+
+impl StdError for ActivityError {}
+
+/// The service a [`Listening`] activity is being played from.
+///
+/// See [`Activity::music_source`].
+///
+/// [`Listening`]: ActivityType::Listening
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum MusicSource {
+    /// Spotify, through Discord's own integration of it.
+    Spotify,
+    /// YouTube Music, from an activity named "YouTube Music".
+    YouTubeMusic,
+    /// Apple Music, from an activity named "Apple Music".
+    AppleMusic,
+    /// Any other service, or a rich presence application not recognised as
+    /// one of the above.
+    Other,
+}
+
+/// The service a [`Streaming`] activity is being streamed to.
+///
+/// See [`Activity::streaming_platform`].
+///
+/// [`Streaming`]: ActivityType::Streaming
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum StreamingPlatform {
+    /// Twitch, at `twitch.tv`.
+    Twitch,
+    /// YouTube, at `youtube.com` or `youtu.be`.
+    YouTube,
+    /// Facebook Gaming, at `facebook.com` or `fb.gg`.
+    Facebook,
+    /// Any other service, with the host of the stream's URL.
+    Other(String),
+}
+
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway#activity-object-activity-types).
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub enum ActivityType {
     /// An indicator that the user is playing a game.
@@ -394,6 +1478,64 @@ enum_number!(ActivityType {
     Competing
 });
 
+impl ActivityType {
+    /// Returns the text Discord displays before the name of an activity of
+    /// this type, such as `"Listening to"`.
+    ///
+    /// This is empty for [`Custom`] activities, which are displayed without a
+    /// prefix.
+    ///
+    /// [`Custom`]: ActivityType::Custom
+    #[must_use]
+    pub const fn preposition(self) -> &'static str {
+        match self {
+            ActivityType::Playing => "Playing",
+            ActivityType::Streaming => "Streaming",
+            ActivityType::Listening => "Listening to",
+            ActivityType::Watching => "Watching",
+            ActivityType::Custom => "",
+            ActivityType::Competing => "Competing in",
+            ActivityType::Unknown => "Unknown",
+        }
+    }
+
+    /// Returns an accent colour conventionally associated with activities of
+    /// this type, such as to match Discord's look when rendering activities in
+    /// embeds.
+    ///
+    /// The colours come from the brand of the service most associated with
+    /// the activity type, or from Discord itself:
+    ///
+    /// - [`Playing`]: Discord's blurple, `#5865F2`, from Discord's branding.
+    /// - [`Streaming`]: Discord's streaming purple, `#593695`, used for the
+    ///   status of streaming users.
+    /// - [`Listening`]: Spotify's green, `#1DB954`, from Spotify's branding.
+    /// - [`Watching`]: YouTube's red, `#FF0000`, from YouTube's branding.
+    /// - [`Competing`]: Discord's gold, `#F1C40F`, from its role colours.
+    /// - [`Custom`] and [`Unknown`]: Discord's grey, `#747F8D`, used for the
+    ///   status of offline users.
+    ///
+    /// [`Playing`]: ActivityType::Playing
+    /// [`Streaming`]: ActivityType::Streaming
+    /// [`Listening`]: ActivityType::Listening
+    /// [`Watching`]: ActivityType::Watching
+    /// [`Competing`]: ActivityType::Competing
+    /// [`Custom`]: ActivityType::Custom
+    /// [`Unknown`]: ActivityType::Unknown
+    #[cfg(feature = "utils")]
+    #[must_use]
+    pub const fn accent_colour(self) -> Colour {
+        match self {
+            ActivityType::Playing => crate::utils::colours::branding::BLURPLE,
+            ActivityType::Streaming => Colour(0x593695),
+            ActivityType::Listening => Colour(0x1DB954),
+            ActivityType::Watching => Colour(0xFF0000),
+            ActivityType::Competing => Colour::GOLD,
+            ActivityType::Custom | ActivityType::Unknown => Colour(0x747F8D),
+        }
+    }
+}
+
 impl Default for ActivityType {
     fn default() -> Self {
         ActivityType::Playing
@@ -412,6 +1554,83 @@ pub struct Gateway {
     pub url: String,
 }
 
+/// A gateway URL, with methods for appending the query parameters used when
+/// connecting to it.
+///
+/// # Examples
+///
+/// ```rust
+/// use serenity::model::gateway::GatewayUrl;
+///
+/// let url = GatewayUrl("wss://gateway.discord.gg".to_string())
+///     .append_version(10)
+///     .append_encoding("json")
+///     .to_url()
+///     .unwrap();
+///
+/// assert_eq!(url.as_str(), "wss://gateway.discord.gg/?v=10&encoding=json");
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct GatewayUrl(pub String);
+
+impl GatewayUrl {
+    /// Appends the gateway API version to use.
+    #[must_use]
+    pub fn append_version(self, v: u64) -> Self {
+        self.append("v", &v.to_string())
+    }
+
+    /// Appends the payload encoding to use, such as `json`.
+    #[must_use]
+    pub fn append_encoding(self, enc: &str) -> Self {
+        self.append("encoding", enc)
+    }
+
+    /// Appends the transport compression to use, if `compress` is `true`.
+    ///
+    /// The only compression supported by Discord is `zlib-stream`.
+    #[must_use]
+    pub fn append_compress(self, compress: bool) -> Self {
+        if compress {
+            self.append("compress", "zlib-stream")
+        } else {
+            self
+        }
+    }
+
+    /// Parses the gateway URL, including any appended query parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`url::ParseError`] if the URL is invalid.
+    pub fn to_url(&self) -> StdResult<Url, url::ParseError> {
+        Url::parse(&self.0)
+    }
+
+    fn append(mut self, key: &str, value: &str) -> Self {
+        let separator = if self.0.contains('?') { '&' } else { '?' };
+
+        self.0.push(separator);
+        self.0.push_str(key);
+        self.0.push('=');
+        self.0.extend(url::form_urlencoded::byte_serialize(value.as_bytes()));
+
+        self
+    }
+}
+
+impl From<Gateway> for GatewayUrl {
+    fn from(gateway: Gateway) -> Self {
+        Self(gateway.url)
+    }
+}
+
+impl From<BotGateway> for GatewayUrl {
+    fn from(gateway: BotGateway) -> Self {
+        Self(gateway.url)
+    }
+}
+
 /// Information detailing the current active status of a [`User`].
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway#client-status-object).
@@ -422,6 +1641,68 @@ pub struct ClientStatus {
     pub web: Option<OnlineStatus>,
 }
 
+impl ClientStatus {
+    /// Returns the status Discord displays for the user, which is the most
+    /// visible one across all platforms.
+    ///
+    /// Statuses are ranked [`Online`], then [`Idle`], then [`DoNotDisturb`],
+    /// then [`Offline`]. Returns `None` if no platform has a status.
+    ///
+    /// [`Online`]: OnlineStatus::Online
+    /// [`Idle`]: OnlineStatus::Idle
+    /// [`DoNotDisturb`]: OnlineStatus::DoNotDisturb
+    /// [`Offline`]: OnlineStatus::Offline
+    #[must_use]
+    pub fn dominant_status(&self) -> Option<OnlineStatus> {
+        fn visibility(status: OnlineStatus) -> u8 {
+            match status {
+                OnlineStatus::Online => 3,
+                OnlineStatus::Idle => 2,
+                OnlineStatus::DoNotDisturb => 1,
+                _ => 0,
+            }
+        }
+
+        [self.desktop, self.mobile, self.web]
+            .iter()
+            .flatten()
+            .copied()
+            .max_by_key(|s| visibility(*s))
+    }
+
+    /// Returns a single status for the user, resolving differing statuses
+    /// across platforms like the official client.
+    ///
+    /// Statuses take precedence in the order [`DoNotDisturb`], then
+    /// [`Online`], then [`Idle`], then [`Offline`]. Unlike in
+    /// [`Self::dominant_status`], do not disturb wins, as it is chosen by the
+    /// user rather than caused by inactivity. Returns [`Offline`] if no
+    /// platform has a status.
+    ///
+    /// [`DoNotDisturb`]: OnlineStatus::DoNotDisturb
+    /// [`Online`]: OnlineStatus::Online
+    /// [`Idle`]: OnlineStatus::Idle
+    /// [`Offline`]: OnlineStatus::Offline
+    #[must_use]
+    pub fn effective_status(&self) -> OnlineStatus {
+        fn precedence(status: OnlineStatus) -> u8 {
+            match status {
+                OnlineStatus::DoNotDisturb => 3,
+                OnlineStatus::Online => 2,
+                OnlineStatus::Idle => 1,
+                _ => 0,
+            }
+        }
+
+        [self.desktop, self.mobile, self.web]
+            .iter()
+            .flatten()
+            .copied()
+            .max_by_key(|s| precedence(*s))
+            .map_or(OnlineStatus::Offline, |s| s.as_seen_by_others())
+    }
+}
+
 /// Information about the user of a [`Presence`] event.
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway-events#presence-update).
@@ -481,26 +1762,120 @@ impl PresenceUser {
         })
     }
 
-    #[cfg(feature = "cache")] // method is only used with the cache feature enabled
-    pub(crate) fn update_with_user(&mut self, user: User) {
-        self.id = user.id;
-        if let Some(avatar) = user.avatar {
-            self.avatar = Some(avatar);
-        }
-        self.bot = Some(user.bot);
-        self.discriminator = Some(user.discriminator);
-        self.name = Some(user.name);
-        if let Some(public_flags) = user.public_flags {
-            self.public_flags = Some(public_flags);
+    /// Returns the discriminator as displayed after the username, such as
+    /// `#0042`.
+    ///
+    /// Users migrated to the new username system have a discriminator of `0`,
+    /// and are displayed without one, so this is an empty string for them, as
+    /// well as when the discriminator is unknown.
+    #[must_use]
+    pub fn discriminator_display(&self) -> String {
+        match self.discriminator {
+            Some(discriminator) if discriminator > 0 => format!("#{:04}", discriminator),
+            _ => String::new(),
         }
     }
-}
 
-/// Information detailing the current online status of a [`User`].
-///
-/// [Discord docs](https://discord.com/developers/docs/topics/gateway#presence-update-presence-update-event-fields).
-#[derive(Clone, Debug, Deserialize, Serialize)]
-#[non_exhaustive]
+    /// Returns the user's distinct identifier, such as `name#0042`, or just
+    /// the username for users migrated to the new username system.
+    ///
+    /// Returns `None` if the username is unknown, as presence updates only
+    /// include the user fields that changed.
+    #[must_use]
+    pub fn tag(&self) -> Option<String> {
+        Some(format!("{}{}", self.name.as_ref()?, self.discriminator_display()))
+    }
+
+    /// Applies a partial update, setting only the fields that are [`Some`] in
+    /// `other`.
+    ///
+    /// Presence updates only include the user fields that changed, so this is
+    /// the method to use when applying one to a previously known user. Fields
+    /// missing from `other` keep their current values.
+    ///
+    /// Use [`Self::overwrite_from`] to replace every field instead.
+    pub fn patch_from(&mut self, other: &PresenceUser) {
+        self.id = other.id;
+        if let Some(avatar) = &other.avatar {
+            self.avatar = Some(avatar.clone());
+        }
+        if let Some(bot) = other.bot {
+            self.bot = Some(bot);
+        }
+        if let Some(discriminator) = other.discriminator {
+            self.discriminator = Some(discriminator);
+        }
+        if let Some(email) = &other.email {
+            self.email = Some(email.clone());
+        }
+        if let Some(mfa_enabled) = other.mfa_enabled {
+            self.mfa_enabled = Some(mfa_enabled);
+        }
+        if let Some(name) = &other.name {
+            self.name = Some(name.clone());
+        }
+        if let Some(verified) = other.verified {
+            self.verified = Some(verified);
+        }
+        if let Some(public_flags) = other.public_flags {
+            self.public_flags = Some(public_flags);
+        }
+    }
+
+    /// Replaces every field with the ones in `other`, including fields that
+    /// are [`None`] in `other`.
+    ///
+    /// Only use this when `other` is known to be complete, as applying a
+    /// partial presence update this way discards the fields it does not
+    /// include. Use [`Self::patch_from`] for partial updates.
+    pub fn overwrite_from(&mut self, other: PresenceUser) {
+        *self = other;
+    }
+
+    /// Sets the avatar hash, returning the previous one.
+    ///
+    /// Compare the returned value to the new one to know whether it changed.
+    pub fn update_avatar_hash(&mut self, hash: String) -> Option<String> {
+        self.avatar.replace(hash)
+    }
+
+    /// Sets the username, returning the previous one.
+    pub fn update_username(&mut self, name: String) -> Option<String> {
+        self.name.replace(name)
+    }
+
+    /// Sets the discriminator, returning the previous one.
+    pub fn update_discriminator(&mut self, discriminator: u16) -> Option<u16> {
+        self.discriminator.replace(discriminator)
+    }
+
+    /// Sets the public flags, returning the previous ones.
+    pub fn update_public_flags(&mut self, flags: UserPublicFlags) -> Option<UserPublicFlags> {
+        self.public_flags.replace(flags)
+    }
+}
+
+impl From<User> for PresenceUser {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            avatar: user.avatar,
+            bot: Some(user.bot),
+            discriminator: Some(user.discriminator),
+            email: None,
+            mfa_enabled: None,
+            name: Some(user.name),
+            verified: None,
+            public_flags: user.public_flags,
+        }
+    }
+}
+
+/// Information detailing the current online status of a [`User`].
+///
+/// [Discord docs](https://discord.com/developers/docs/topics/gateway#presence-update-presence-update-event-fields).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct Presence {
     /// [`User`]'s current activities.
     #[serde(default)]
@@ -510,10 +1885,638 @@ pub struct Presence {
     pub client_status: Option<ClientStatus>,
     /// The `GuildId` the presence update is coming from.
     pub guild_id: Option<GuildId>,
+    /// Unix time in milliseconds of when the user went idle, if sent.
+    #[serde(default)]
+    pub since: Option<u64>,
     /// The user's online status.
     pub status: OnlineStatus,
     /// Data about the associated user.
     pub user: PresenceUser,
+    /// The fields sent by Discord that are not modelled by this struct, such
+    /// as newly added or undocumented ones.
+    ///
+    /// These are kept as is when serializing the presence, but are never sent
+    /// to the gateway in a presence update. Keys of modelled fields must not
+    /// be inserted, as they would be serialized twice.
+    ///
+    /// Requires the `presence_extra_fields` feature.
+    #[cfg(feature = "presence_extra_fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Presence {
+    /// Removes duplicated activities, keeping the first occurrence of each.
+    ///
+    /// Two activities are considered duplicates if their kind, name and state
+    /// are equal. Some third-party clients are known to send the same activity
+    /// more than once.
+    pub fn dedup_activities(&mut self) {
+        let mut activities: Vec<Activity> = Vec::with_capacity(self.activities.len());
+
+        for activity in std::mem::take(&mut self.activities) {
+            let duplicate = activities.iter().any(|a| {
+                a.kind == activity.kind && a.name == activity.name && a.state == activity.state
+            });
+
+            if !duplicate {
+                activities.push(activity);
+            }
+        }
+
+        self.activities = activities;
+    }
+
+    /// Clones the presence, stripping data that should not end up in logs.
+    ///
+    /// The secrets and application Id of every activity are removed, and
+    /// activity names and details longer than 50 characters are truncated and
+    /// suffixed with an ellipsis. With the `presence_extra_fields` feature,
+    /// unknown fields of the presence and its activities are dropped as well.
+    #[must_use]
+    pub fn clone_stripped(&self) -> Presence {
+        const MAX_LENGTH: usize = 50;
+
+        let mut presence = self.clone();
+
+        #[cfg(feature = "presence_extra_fields")]
+        presence.extra.clear();
+
+        for activity in &mut presence.activities {
+            activity.secrets = None;
+            activity.application_id = None;
+            activity.name = truncate(&activity.name, MAX_LENGTH);
+            activity.details = activity.details.as_deref().map(|d| truncate(d, MAX_LENGTH));
+
+            #[cfg(feature = "presence_extra_fields")]
+            activity.extra.clear();
+        }
+
+        presence
+    }
+
+    /// Returns where the presence was received from: a guild shared with the
+    /// user, or outside of any guild, such as for friends.
+    #[must_use]
+    pub fn origin(&self) -> PresenceOrigin {
+        match self.guild_id {
+            Some(guild_id) => PresenceOrigin::Guild(guild_id),
+            None => PresenceOrigin::Global,
+        }
+    }
+
+    /// Whether this presence and `other` were both received from the same
+    /// guild.
+    ///
+    /// Returns `false` if either presence has no guild, such as presences of
+    /// friends.
+    #[must_use]
+    pub fn in_same_guild(&self, other: &Presence) -> bool {
+        self.guild_id.is_some() && self.guild_id == other.guild_id
+    }
+
+    /// Whether any of this presence's activities belongs to the same
+    /// application as one of `other`'s activities.
+    ///
+    /// Activities without an application Id are ignored.
+    #[must_use]
+    pub fn shares_activity(&self, other: &Presence) -> bool {
+        self.activities
+            .iter()
+            .filter_map(|a| a.application_id)
+            .any(|id| other.activities.iter().any(|a| a.application_id == Some(id)))
+    }
+
+    /// Heuristically detects whether the user is currently in a voice call.
+    ///
+    /// Discord does not expose this in presences, so this returns `true` if
+    /// both of the following hold:
+    ///
+    /// - the user is not offline, and is active on at least one device if
+    ///   [`Self::client_status`] is known;
+    /// - one of the user's activities has the
+    ///   [`ActivityFlags::PARTY_PRIVACY_VOICE_CHANNEL`] or
+    ///   [`ActivityFlags::EMBEDDED`] flag, as set by activities whose party is
+    ///   a voice channel and by activities launched in a voice channel.
+    ///
+    /// Calls without such an activity, and presences with missing activity
+    /// flags, are not detected, so `false` does not mean the user is not in a
+    /// call.
+    #[must_use]
+    pub fn in_voice_call(&self) -> bool {
+        if self.status == OnlineStatus::Offline {
+            return false;
+        }
+
+        if let Some(client_status) = &self.client_status {
+            let devices = [client_status.desktop, client_status.mobile, client_status.web];
+
+            if devices.iter().all(|s| matches!(s, None | Some(OnlineStatus::Offline))) {
+                return false;
+            }
+        }
+
+        let voice_flags = ActivityFlags::PARTY_PRIVACY_VOICE_CHANNEL | ActivityFlags::EMBEDDED;
+
+        self.activities.iter().any(|a| matches!(a.flags, Some(f) if f.intersects(voice_flags)))
+    }
+
+    /// Whether the user is AFK, meaning that they are [`Idle`] and the
+    /// presence has a [`Self::since`] timestamp.
+    ///
+    /// Discord does not document the `since` field of received presences, and
+    /// does not always send it, notably for guild presences received without
+    /// the `GUILD_PRESENCES` intent. A user may therefore be AFK even if this
+    /// returns `false`.
+    ///
+    /// [`Idle`]: OnlineStatus::Idle
+    #[must_use]
+    pub fn is_afk(&self) -> bool {
+        self.status == OnlineStatus::Idle && self.since.is_some()
+    }
+
+    /// Returns when the user went idle, if the presence has a
+    /// [`Self::since`] timestamp.
+    ///
+    /// Refer to [`Self::is_afk`] for the accuracy of this value.
+    #[must_use]
+    pub fn afk_since(&self) -> Option<SystemTime> {
+        self.since.map(|since| SystemTime::UNIX_EPOCH + Duration::from_millis(since))
+    }
+
+    /// Resolves the full [`User`] this presence belongs to.
+    ///
+    /// Presence updates often only contain part of the user's data, so this
+    /// first uses [`PresenceUser::to_user`], then the cache, and as a last
+    /// resort requests the user via the REST API. A user fetched via the REST
+    /// API is stored in the cache, to avoid fetching it again.
+    ///
+    /// Returns `Ok(None)` if the user does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Http`] if the user could not be fetched for any
+    /// other reason.
+    #[cfg(feature = "model")]
+    pub async fn resolve_user(&self, cache_http: impl CacheHttp) -> Result<Option<User>> {
+        if let Some(user) = self.user.to_user() {
+            return Ok(Some(user));
+        }
+
+        #[cfg(feature = "cache")]
+        {
+            if let Some(cache) = cache_http.cache() {
+                if let Some(user) = cache.user(self.user.id) {
+                    return Ok(Some(user));
+                }
+            }
+        }
+
+        match cache_http.http().get_user(self.user.id.0).await {
+            Ok(user) => {
+                #[cfg(feature = "cache")]
+                {
+                    if let Some(cache) = cache_http.cache() {
+                        cache.update_user_entry(&user);
+                    }
+                }
+
+                Ok(Some(user))
+            },
+            Err(Error::Http(why)) if why.status_code() == Some(StatusCode::NOT_FOUND) => Ok(None),
+            Err(why) => Err(why),
+        }
+    }
+
+    /// Returns a coloured circle emoji representing the user's status.
+    ///
+    /// Refer to [`OnlineStatus::status_emoji`] for more information.
+    #[must_use]
+    pub fn status_emoji(&self) -> &'static str {
+        self.status.status_emoji()
+    }
+
+    /// Returns the status Discord displays for the user.
+    ///
+    /// This is the [dominant status] across the user's platforms if known,
+    /// falling back to [`Self::status`].
+    ///
+    /// [dominant status]: ClientStatus::dominant_status
+    #[must_use]
+    pub fn primary_status(&self) -> OnlineStatus {
+        self.client_status.as_ref().and_then(ClientStatus::dominant_status).unwrap_or(self.status)
+    }
+
+    /// Converts this presence into the form sent to the gateway when updating
+    /// the current user's presence.
+    ///
+    /// Fields that are only present on received presences, such as
+    /// [`Self::guild_id`] and [`Self::client_status`], are dropped.
+    #[must_use]
+    pub fn to_outbound(&self, afk: bool) -> OutboundPresence {
+        OutboundPresence {
+            since: None,
+            activities: self.activities.clone(),
+            status: self.status,
+            afk,
+        }
+    }
+
+    /// Returns an iterator over the presence's activities.
+    pub fn iter(&self) -> std::slice::Iter<'_, Activity> {
+        self.activities.iter()
+    }
+
+    /// Returns an iterator that allows modifying the presence's activities.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Activity> {
+        self.activities.iter_mut()
+    }
+
+    /// Consumes the presence, returning its activities.
+    #[must_use]
+    pub fn into_activities(self) -> Vec<Activity> {
+        self.activities
+    }
+
+    /// Compares this presence to an `old` one of the same user, detecting
+    /// which parts of it meaningfully changed.
+    ///
+    /// Activities are compared by their kind and name only, so that updates
+    /// to e.g. their timestamps or state are not considered changes. User
+    /// fields that are missing from this presence, as is common in partial
+    /// presence updates, are not considered changed either.
+    ///
+    /// Streams are compared by their URL, so that a stream continuing under a
+    /// new title is not considered to have gone live again.
+    #[must_use]
+    pub fn changed_from<'a>(&'a self, old: &Presence) -> PresenceChange<'a> {
+        fn activity_key(a: &Activity) -> (ActivityType, &str) {
+            (a.kind, &a.name)
+        }
+
+        fn stream_urls(p: &Presence) -> impl Iterator<Item = Option<&Url>> {
+            p.activities
+                .iter()
+                .filter(|a| a.kind == ActivityType::Streaming)
+                .map(|a| a.url.as_ref())
+        }
+
+        let platforms =
+            |p: &Presence| p.client_status.as_ref().map(|c| (c.desktop, c.mobile, c.web));
+
+        let (new_user, old_user) = (&self.user, &old.user);
+
+        PresenceChange {
+            status_changed: self.status != old.status,
+            activities_changed: !self
+                .activities
+                .iter()
+                .map(activity_key)
+                .eq(old.activities.iter().map(activity_key)),
+            platform_changed: platforms(self) != platforms(old),
+            user_data_changed: new_user.id != old_user.id
+                || changed(new_user.avatar.as_ref(), old_user.avatar.as_ref())
+                || changed(new_user.bot.as_ref(), old_user.bot.as_ref())
+                || changed(new_user.discriminator.as_ref(), old_user.discriminator.as_ref())
+                || changed(new_user.email.as_ref(), old_user.email.as_ref())
+                || changed(new_user.mfa_enabled.as_ref(), old_user.mfa_enabled.as_ref())
+                || changed(new_user.name.as_ref(), old_user.name.as_ref())
+                || changed(new_user.verified.as_ref(), old_user.verified.as_ref())
+                || changed(new_user.public_flags.as_ref(), old_user.public_flags.as_ref()),
+            went_live: self.activities.iter().find(|a| {
+                a.kind == ActivityType::Streaming
+                    && !stream_urls(old).any(|url| url == a.url.as_ref())
+            }),
+            stopped_streaming: stream_urls(old)
+                .any(|old_url| !stream_urls(self).any(|url| url == old_url)),
+        }
+    }
+
+    /// Creates an embed summarising the presence.
+    ///
+    /// The embed's title is the user's name, its colour is the one of the
+    /// user's online status as given by [`OnlineStatus::color`], each activity
+    /// is listed as a field, and the footer lists the platforms the user is
+    /// active on, if known.
+    #[cfg(all(feature = "model", feature = "builder"))]
+    #[must_use]
+    pub fn summary_embed(&self) -> CreateEmbed {
+        let mut embed = CreateEmbed::default();
+
+        match &self.user.name {
+            Some(name) => embed.title(name),
+            None => embed.title(self.user.id),
+        };
+
+        embed.colour(self.status.color());
+
+        for activity in &self.activities {
+            let name = match activity.kind {
+                ActivityType::Custom => "Custom Status".to_string(),
+                kind => format!("{} {}", kind.preposition(), activity.name),
+            };
+            let lines = [activity.details.as_deref(), activity.state.as_deref()];
+            let value = lines.iter().flatten().copied().collect::<Vec<_>>().join("\n");
+
+            // Field values may not be empty.
+            embed.field(name, if value.is_empty() { "\u{200B}".to_string() } else { value }, false);
+        }
+
+        if let Some(client_status) = &self.client_status {
+            let platforms = [
+                ("Desktop", client_status.desktop),
+                ("Mobile", client_status.mobile),
+                ("Web", client_status.web),
+            ];
+            let active = platforms
+                .iter()
+                .filter(|(_, status)| status.is_some())
+                .map(|(platform, _)| *platform)
+                .collect::<Vec<_>>();
+
+            if !active.is_empty() {
+                embed.footer(|f| f.text(format!("Active on {}", active.join(", "))));
+            }
+        }
+
+        embed
+    }
+
+    /// Serializes the presence to a compact binary representation, which can
+    /// be read back with [`Self::from_compact_bytes`].
+    ///
+    /// The presence is encoded as MessagePack, which is typically around two
+    /// thirds the size of its JSON form. Unlike keeping the deserialized
+    /// presence, reading it back costs CPU time on every access, so this is
+    /// worth it when holding many presences in memory that are rarely read.
+    ///
+    /// **Note**: The representation is only meant to be read back by the same
+    /// version of the library.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the presence could not be encoded.
+    #[cfg(feature = "compact_presence")]
+    pub fn to_compact_bytes(&self) -> StdResult<Vec<u8>, rmp_serde::encode::Error> {
+        // Field names are kept, as skipped optional fields would otherwise
+        // shift the following ones.
+        rmp_serde::to_vec_named(self)
+    }
+
+    /// Deserializes a presence serialized with [`Self::to_compact_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid presence.
+    #[cfg(feature = "compact_presence")]
+    pub fn from_compact_bytes(bytes: &[u8]) -> StdResult<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+}
+
+/// Where a [`Presence`] was received from, as returned by
+/// [`Presence::origin`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum PresenceOrigin {
+    /// The presence of a member of a guild shared with the current user.
+    Guild(GuildId),
+    /// A presence received outside of any guild, such as that of a friend.
+    Global,
+}
+
+/// Whether a field present in a partial update differs from its old value.
+fn changed<T: PartialEq>(new: Option<&T>, old: Option<&T>) -> bool {
+    new.is_some() && new != old
+}
+
+/// The parts of a [`Presence`] that changed, as detected by
+/// [`Presence::changed_from`].
+#[derive(Clone, Copy, Debug, Default)]
+#[non_exhaustive]
+pub struct PresenceChange<'a> {
+    /// Whether the online status changed.
+    pub status_changed: bool,
+    /// Whether an activity started or ended, by kind and name.
+    pub activities_changed: bool,
+    /// Whether the online status on any platform changed.
+    pub platform_changed: bool,
+    /// Whether any of the user's data changed.
+    pub user_data_changed: bool,
+    /// The [`Streaming`] activity of a stream that started, if any.
+    ///
+    /// [`Streaming`]: ActivityType::Streaming
+    pub went_live: Option<&'a Activity>,
+    /// Whether a stream ended. This is also the case when the user switched
+    /// to a different stream, in which case [`Self::went_live`] is set too.
+    pub stopped_streaming: bool,
+}
+
+impl PresenceChange<'_> {
+    /// Whether anything changed at all.
+    #[must_use]
+    pub fn any(&self) -> bool {
+        self.status_changed
+            || self.activities_changed
+            || self.platform_changed
+            || self.user_data_changed
+            || self.went_live.is_some()
+            || self.stopped_streaming
+    }
+}
+
+impl<'a> IntoIterator for &'a Presence {
+    type Item = &'a Activity;
+    type IntoIter = std::slice::Iter<'a, Activity>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Presence {
+    type Item = &'a mut Activity;
+    type IntoIter = std::slice::IterMut<'a, Activity>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// A reduced form of a [`Presence`], keeping only the data most commonly
+/// needed from it.
+///
+/// Activity names are shared through an interner, as many users have the
+/// same activity at once. Use [`Self::to_presence`] to rebuild an
+/// approximate [`Presence`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct CompactPresence {
+    /// The Id of the user the presence belongs to.
+    pub user_id: UserId,
+    /// The `GuildId` the presence update came from.
+    pub guild_id: Option<GuildId>,
+    /// The user's online status.
+    pub status: OnlineStatus,
+    /// The user's current activities.
+    pub activities: Vec<CompactActivity>,
+}
+
+/// A reduced form of an [`Activity`], as stored in a [`CompactPresence`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct CompactActivity {
+    /// The type of activity.
+    pub kind: ActivityType,
+    /// The name of the activity, shared with other activities of the same
+    /// name.
+    pub name: Arc<str>,
+    /// The user's current party status.
+    pub state: Option<String>,
+    /// Unix timestamps for the start and/or end times of the activity.
+    pub timestamps: Option<ActivityTimestamps>,
+}
+
+impl CompactPresence {
+    /// Reduces a [`Presence`], passing each activity name through `intern`
+    /// so that equal names can share a single allocation.
+    pub fn new(presence: &Presence, mut intern: impl FnMut(&str) -> Arc<str>) -> Self {
+        Self {
+            user_id: presence.user.id,
+            guild_id: presence.guild_id,
+            status: presence.status,
+            activities: presence
+                .activities
+                .iter()
+                .map(|activity| CompactActivity {
+                    kind: activity.kind,
+                    name: intern(&activity.name),
+                    state: activity.state.clone(),
+                    timestamps: activity.timestamps.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Rebuilds an approximate [`Presence`].
+    ///
+    /// Only the user's Id is known, and any data not kept by this struct,
+    /// such as activity assets or the client status, is left empty.
+    #[must_use]
+    pub fn to_presence(&self) -> Presence {
+        Presence {
+            activities: self
+                .activities
+                .iter()
+                .map(|compact| Activity {
+                    state: compact.state.clone(),
+                    timestamps: compact.timestamps.clone(),
+                    ..Activity::new(compact.name.to_string(), compact.kind)
+                })
+                .collect(),
+            client_status: None,
+            guild_id: self.guild_id,
+            since: None,
+            status: self.status,
+            user: PresenceUser {
+                id: self.user_id,
+                ..PresenceUser::default()
+            },
+            #[cfg(feature = "presence_extra_fields")]
+            extra: HashMap::new(),
+        }
+    }
+}
+
+/// A presence in the format expected by the gateway for outbound presence
+/// updates.
+///
+/// [Discord docs](https://discord.com/developers/docs/topics/gateway-events#update-presence-gateway-presence-update-structure).
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct OutboundPresence {
+    /// Unix time in milliseconds of when the client went idle, if it is idle.
+    pub since: Option<u64>,
+    /// The current user's activities, serialized in order.
+    ///
+    /// Discord clients show the first activity as the main one, such as in
+    /// the member list, while a [`Custom`] status is shown apart from the
+    /// others, so it is usually placed last.
+    ///
+    /// [`Custom`]: ActivityType::Custom
+    #[serde(serialize_with = "serialize_outbound_activities")]
+    pub activities: Vec<Activity>,
+    /// The current user's online status.
+    pub status: OnlineStatus,
+    /// Whether the client is AFK.
+    pub afk: bool,
+}
+
+#[cfg(feature = "model")]
+impl OutboundPresence {
+    /// Checks that the activities would be accepted by the gateway.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ActivityError::TooManyButtons`] if an activity has more than
+    /// 2 buttons.
+    pub fn validate(&self) -> StdResult<(), ActivityError> {
+        self.activities.iter().try_for_each(Activity::validate_buttons)
+    }
+
+    /// Places `activity` first, making it the main activity shown by Discord
+    /// clients. The order of the other activities is kept.
+    ///
+    /// An activity of the same type and name is replaced.
+    #[must_use]
+    pub fn with_primary(mut self, activity: Activity) -> Self {
+        self.activities.retain(|a| a.kind != activity.kind || a.name != activity.name);
+        self.activities.insert(0, activity);
+
+        self
+    }
+}
+
+impl From<Presence> for OutboundPresence {
+    fn from(presence: Presence) -> Self {
+        Self {
+            since: None,
+            activities: presence.activities,
+            status: presence.status,
+            afk: false,
+        }
+    }
+}
+
+/// The full gateway payload used to update the current user's presence.
+///
+/// This serializes to the `{"op": 3, "d": {...}}` envelope expected by the
+/// gateway, and can be sent as-is over a raw WebSocket connection.
+///
+/// [Discord docs](https://discord.com/developers/docs/topics/gateway-events#update-presence).
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct PresenceUpdatePayload {
+    op: OpCode,
+    /// The presence to update to.
+    #[serde(rename = "d")]
+    pub presence: OutboundPresence,
+}
+
+impl PresenceUpdatePayload {
+    /// Creates a new payload updating the presence to the given one.
+    pub fn new(presence: impl Into<OutboundPresence>) -> Self {
+        Self {
+            op: OpCode::StatusUpdate,
+            presence: presence.into(),
+        }
+    }
+}
+
+impl From<OutboundPresence> for PresenceUpdatePayload {
+    fn from(presence: OutboundPresence) -> Self {
+        Self::new(presence)
+    }
 }
 
 /// An initial set of information given after IDENTIFYing to the gateway.
@@ -528,6 +2531,12 @@ pub struct Ready {
     pub presences: HashMap<UserId, Presence>,
     #[serde(default, with = "private_channels")]
     pub private_channels: HashMap<ChannelId, Channel>,
+    /// The current user's relationships. Only sent to user accounts.
+    #[serde(default)]
+    pub relationships: Vec<Relationship>,
+    /// The URL to connect to when resuming this session.
+    #[serde(default)]
+    pub resume_gateway_url: Option<String>,
     pub session_id: String,
     pub shard: Option<[u64; 2]>,
     #[serde(default, rename = "_trace")]
@@ -537,25 +2546,164 @@ pub struct Ready {
     pub version: u64,
 }
 
-/// Information describing how many gateway sessions you can initiate within a
-/// ratelimit period.
-///
-/// [Discord docs](https://discord.com/developers/docs/topics/gateway#session-start-limit-object-session-start-limit-structure).
-#[derive(Clone, Debug, Deserialize, Serialize)]
-#[non_exhaustive]
-pub struct SessionStartLimit {
-    /// The number of sessions that you can still initiate within the current
-    /// ratelimit period.
+impl Ready {
+    /// Whether the gateway version of this session is the one the library was
+    /// built against, [`GATEWAY_VERSION`].
+    ///
+    /// A mismatch usually means Discord upgraded the gateway, and payloads
+    /// may differ from what the library expects.
+    ///
+    /// [`GATEWAY_VERSION`]: crate::constants::GATEWAY_VERSION
+    #[inline]
+    #[must_use]
+    pub fn is_supported_version(&self) -> bool {
+        self.version == u64::from(crate::constants::GATEWAY_VERSION)
+    }
+
+    /// Whether the current user has any kind of Nitro subscription.
+    ///
+    /// Refer to [`PremiumType::is_nitro`] for how unknown subscription types
+    /// are handled.
+    #[inline]
+    #[must_use]
+    pub fn has_nitro(&self) -> bool {
+        self.user.premium_type.is_nitro()
+    }
+
+    /// Retrieves the partial information about the current application.
+    #[inline]
+    #[must_use]
+    pub fn partial_application(&self) -> &PartialCurrentApplicationInfo {
+        &self.application
+    }
+
+    /// Retrieves the Id of the current application.
+    ///
+    /// This is a shortcut for `ready.application.id`, commonly needed when
+    /// registering application commands in the ready handler.
+    #[inline]
+    #[must_use]
+    pub fn application_id(&self) -> ApplicationId {
+        self.application.id
+    }
+
+    /// Retrieves the flags of the current application.
+    #[inline]
+    #[must_use]
+    pub fn application_flags(&self) -> ApplicationFlags {
+        self.application.flags
+    }
+
+    /// Retrieves the Ids of the guilds the current user is in.
+    #[must_use]
+    pub fn guild_ids(&self) -> Vec<GuildId> {
+        self.guilds.iter().map(|g| g.id).collect()
+    }
+
+    /// Retrieves the Ids of the private channels the current user is in.
+    #[inline]
+    #[must_use]
+    pub fn private_channel_ids(&self) -> Vec<ChannelId> {
+        self.private_channels.keys().copied().collect()
+    }
+
+    /// Retrieves the number of private channels the current user is in.
+    #[inline]
+    #[must_use]
+    pub fn private_channel_count(&self) -> usize {
+        self.private_channels.len()
+    }
+
+    /// Retrieves the Ids of the users whose presences were received.
+    #[inline]
+    #[must_use]
+    pub fn presence_user_ids(&self) -> Vec<UserId> {
+        self.presences.keys().copied().collect()
+    }
+
+    /// Retrieves the number of presences that were received.
+    #[inline]
+    #[must_use]
+    pub fn presence_count(&self) -> usize {
+        self.presences.len()
+    }
+
+    /// Whether the guild is handled by the shard of this session.
+    ///
+    /// This is always the case if the session is not sharded.
+    #[cfg(feature = "utils")]
+    #[must_use]
+    pub fn owns_guild(&self, guild_id: GuildId) -> bool {
+        match self.shard {
+            Some([shard_id, total_shards]) if total_shards > 0 => {
+                Self::shard_for_guild(guild_id, total_shards) == shard_id
+            },
+            _ => true,
+        }
+    }
+
+    /// Calculates the Id of the shard handling a guild, given the total number
+    /// of shards.
+    ///
+    /// This is a shortcut for [`utils::shard_id`].
+    ///
+    /// [`utils::shard_id`]: crate::utils::shard_id
+    #[cfg(feature = "utils")]
+    #[inline]
+    #[must_use]
+    pub fn shard_for_guild(guild_id: GuildId, total_shards: u64) -> u64 {
+        crate::utils::shard_id(guild_id.0, total_shards)
+    }
+}
+
+/// Information describing how many gateway sessions you can initiate within a
+/// ratelimit period.
+///
+/// [Discord docs](https://discord.com/developers/docs/topics/gateway#session-start-limit-object-session-start-limit-structure).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct SessionStartLimit {
+    /// The number of sessions that you can still initiate within the current
+    /// ratelimit period.
     pub remaining: u64,
     /// The number of milliseconds until the ratelimit period resets.
+    #[serde(alias = "resetAfter")]
     pub reset_after: u64,
     /// The total number of session starts within the ratelimit period allowed.
     pub total: u64,
     /// The number of identify requests allowed per 5 seconds.
+    #[serde(alias = "maxConcurrency")]
     pub max_concurrency: u64,
 }
+
+#[cfg(feature = "model")]
+impl SessionStartLimit {
+    /// Whether `n` more sessions can be started within the current ratelimit
+    /// period.
+    #[must_use]
+    pub fn can_start(&self, n: u64) -> bool {
+        self.remaining >= n
+    }
+
+    /// Returns how long to wait before starting `n` more sessions.
+    ///
+    /// Returns `None` if enough sessions remain to start them right away, or
+    /// the time until the ratelimit period resets otherwise.
+    #[must_use]
+    pub fn throttle_start(&self, n: u64) -> Option<Duration> {
+        if self.can_start(n) {
+            None
+        } else {
+            Some(Duration::from_millis(self.reset_after))
+        }
+    }
+}
+
 /// Timestamps of when a user started and/or is ending their activity.
 ///
+/// The raw fields are Unix timestamps in milliseconds. Use
+/// [`Self::start_time`] and [`Self::end_time`] to get them as [`Timestamp`]s.
+///
 /// [Discord docs](https://discord.com/developers/docs/game-sdk/activities#data-models-activitytimestamps-struct).
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[non_exhaustive]
@@ -564,3 +2712,1337 @@ pub struct ActivityTimestamps {
     pub start: Option<u64>,
 }
 
+impl ActivityTimestamps {
+    /// Returns the start of the activity, if any.
+    #[must_use]
+    pub fn start_time(&self) -> Option<Timestamp> {
+        self.start.and_then(millis_to_timestamp)
+    }
+
+    /// Returns the end of the activity, if any.
+    #[must_use]
+    pub fn end_time(&self) -> Option<Timestamp> {
+        self.end.and_then(millis_to_timestamp)
+    }
+}
+
+fn millis_to_timestamp(millis: u64) -> Option<Timestamp> {
+    Timestamp::from_unix_millis(i64::try_from(millis).ok()?).ok()
+}
+
+#[cfg(feature = "model")]
+impl ActivityTimestamps {
+    /// Creates a new set of timestamps, in Unix milliseconds.
+    #[must_use]
+    pub fn new(start: Option<u64>, end: Option<u64>) -> Self {
+        Self {
+            end,
+            start,
+        }
+    }
+
+    /// Creates a set of timestamps starting at the current time, with no end.
+    ///
+    /// This makes Discord display the time elapsed since the activity started.
+    #[must_use]
+    pub fn now() -> Self {
+        Self::new(Some(unix_millis_now()), None)
+    }
+
+    /// Sets the end timestamp to `duration` after the start timestamp.
+    ///
+    /// If no start timestamp is set, the current time is used instead. The
+    /// end saturates at [`u64::MAX`] rather than overflowing.
+    #[must_use]
+    pub fn with_end_in(mut self, duration: Duration) -> Self {
+        let start = self.start.unwrap_or_else(unix_millis_now);
+
+        self.end = Some(start.saturating_add(millis(duration)));
+        self
+    }
+
+    /// Returns how far along the activity is at `now`, from `0.0` at the
+    /// start to `1.0` at the end, such as to draw the progress bar of a song.
+    ///
+    /// The timestamps are Unix times in milliseconds, so `now` should usually
+    /// be [`SystemTime::now`]. Times before the start return `0.0`, and times
+    /// after the end `1.0`.
+    ///
+    /// Returns `None` if the start or the end is missing.
+    #[must_use]
+    // Durations in milliseconds are far below 2^52, so they convert exactly.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn progress(&self, now: SystemTime) -> Option<f64> {
+        let (start, end) = (self.start?, self.end?);
+        let now = now.duration_since(SystemTime::UNIX_EPOCH).map_or(0, millis);
+
+        Some(if now <= start {
+            0.0
+        } else if now >= end {
+            1.0
+        } else {
+            (now - start) as f64 / (end - start) as f64
+        })
+    }
+
+    /// Returns [`Self::progress`] as a percentage, rounded to the nearest
+    /// integer.
+    #[must_use]
+    pub fn progress_percentage(&self, now: SystemTime) -> Option<u8> {
+        self.progress(now).map(|progress| (progress * 100.0).round() as u8)
+    }
+}
+
+/// Converts a duration to milliseconds, saturating at [`u64::MAX`].
+#[cfg(feature = "model")]
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(feature = "model")]
+fn unix_millis_now() -> u64 {
+    SystemTime::UNIX_EPOCH.elapsed().map_or(0, millis)
+}
+
+/// The JSON of a minimal [`Ready`] event, for tests to add fields to.
+#[cfg(test)]
+pub(crate) fn ready_json() -> Value {
+    crate::json::json!({
+        "application": {"id": "1", "flags": 0},
+        "guilds": [],
+        "session_id": "session",
+        "user": {
+            "id": "10",
+            "username": "user",
+            "discriminator": "0001",
+            "mfa_enabled": false,
+        },
+        "v": 10,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use super::ready_json;
+    use crate::json::{self, json};
+    use crate::model::prelude::*;
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_timestamps_progress() {
+        let at = |millis| SystemTime::UNIX_EPOCH + Duration::from_millis(millis);
+        let timestamps = ActivityTimestamps::new(Some(10_000), Some(13_000));
+
+        assert_eq!(timestamps.progress(at(5_000)), Some(0.0));
+        assert_eq!(timestamps.progress(at(11_500)), Some(0.5));
+        assert_eq!(timestamps.progress(at(20_000)), Some(1.0));
+        assert_eq!(timestamps.progress_percentage(at(11_000)), Some(33));
+        assert_eq!(timestamps.progress_percentage(at(12_000)), Some(67));
+
+        assert_eq!(ActivityTimestamps::new(Some(10_000), None).progress(at(11_000)), None);
+        assert_eq!(ActivityTimestamps::new(None, Some(13_000)).progress_percentage(at(0)), None);
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_timestamps_new() {
+        let timestamps = ActivityTimestamps::new(Some(10_000), Some(13_000));
+        assert_eq!(timestamps.start, Some(10_000));
+        assert_eq!(timestamps.end, Some(13_000));
+
+        let before = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_millis() as u64;
+        let timestamps = ActivityTimestamps::now();
+        let after = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_millis() as u64;
+        assert!((before..=after).contains(&timestamps.start.unwrap()));
+        assert_eq!(timestamps.end, None);
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_timestamps_with_end_in() {
+        let timestamps =
+            ActivityTimestamps::new(Some(10_000), None).with_end_in(Duration::from_secs(3));
+        assert_eq!(timestamps.start, Some(10_000));
+        assert_eq!(timestamps.end, Some(13_000));
+
+        let timestamps = ActivityTimestamps::new(Some(10_000), None).with_end_in(Duration::MAX);
+        assert_eq!(timestamps.end, Some(u64::MAX));
+
+        let before = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_millis() as u64;
+        let timestamps = ActivityTimestamps::new(None, None).with_end_in(Duration::from_secs(3));
+        let after = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_millis() as u64;
+        assert_eq!(timestamps.start, None);
+        assert!((before + 3_000..=after + 3_000).contains(&timestamps.end.unwrap()));
+    }
+
+    /// An [`Http`] sending its requests to `addr` instead of Discord.
+    #[cfg(feature = "model")]
+    fn http_at(addr: std::net::SocketAddr) -> crate::http::Http {
+        crate::http::HttpBuilder::new("token")
+            .proxy(format!("http://{}/", addr))
+            .unwrap()
+            .ratelimiter_disabled(true)
+            .build()
+    }
+
+    /// An address nothing listens on, so that any request sent to it fails.
+    #[cfg(feature = "model")]
+    fn unused_addr() -> std::net::SocketAddr {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+    }
+
+    #[cfg(feature = "model")]
+    #[tokio::test]
+    async fn test_presence_resolve_user_full() {
+        let presence: Presence = json::from_value(json!({
+            "user": {"id": "7", "username": "user", "discriminator": "0042", "bot": false},
+            "status": "online",
+        }))
+        .unwrap();
+
+        let http = http_at(unused_addr());
+        let user = presence.resolve_user(&http).await.unwrap().unwrap();
+        assert_eq!(user.id, UserId(7));
+        assert_eq!(user.name, "user");
+    }
+
+    #[cfg(all(feature = "model", feature = "cache"))]
+    #[tokio::test]
+    async fn test_presence_resolve_user_cached() {
+        let presence: Presence =
+            json::from_value(json!({"user": {"id": "7"}, "status": "online"})).unwrap();
+        let user = User {
+            id: UserId(7),
+            name: "cached".to_string(),
+            ..User::default()
+        };
+
+        let cache = std::sync::Arc::new(crate::cache::Cache::default());
+        cache.update_user_entry(&user);
+        let http = http_at(unused_addr());
+
+        let user = presence.resolve_user((&cache, &http)).await.unwrap().unwrap();
+        assert_eq!(user.name, "cached");
+    }
+
+    #[cfg(feature = "model")]
+    #[tokio::test]
+    async fn test_presence_resolve_user_not_found() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let http = http_at(listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let len = stream.read(&mut request).await.unwrap();
+            let body = r#"{"code": 10013, "message": "Unknown User"}"#;
+            let response = format!(
+                "HTTP/1.1 404 Not Found\r\ncontent-type: application/json\r\n\
+                 content-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+
+            String::from_utf8_lossy(&request[..len]).into_owned()
+        });
+
+        let presence: Presence =
+            json::from_value(json!({"user": {"id": "7"}, "status": "online"})).unwrap();
+        assert!(presence.resolve_user(&http).await.unwrap().is_none());
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET /api/"), "{}", request);
+        assert!(request.contains("/users/7 "), "{}", request);
+    }
+
+    #[test]
+    fn test_presence_clone_stripped() {
+        let long = "\u{e9}".repeat(60);
+        let presence: Presence = json::from_value(json!({
+            "user": {"id": "1"},
+            "status": "online",
+            "activities": [{
+                "type": 0,
+                "name": long,
+                "details": "In a match",
+                "application_id": "2",
+                "secrets": {"join": "join-secret", "spectate": "spectate-secret"},
+            }],
+        }))
+        .unwrap();
+
+        let stripped = presence.clone_stripped();
+        let activity = &stripped.activities[0];
+        assert!(activity.secrets.is_none());
+        assert!(activity.application_id.is_none());
+        assert_eq!(activity.name, format!("{}\u{2026}", "\u{e9}".repeat(50)));
+        assert_eq!(activity.details.as_deref(), Some("In a match"));
+
+        // The original presence is left untouched.
+        assert!(presence.activities[0].secrets.is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "presence_extra_fields")]
+    fn test_presence_clone_stripped_extra_fields() {
+        let presence: Presence = json::from_value(json!({
+            "user": {"id": "1"},
+            "status": "online",
+            "activities": [{"type": 0, "name": "Game", "platform": "desktop"}],
+            "client_info": {"os": "linux"},
+        }))
+        .unwrap();
+        assert!(!presence.extra.is_empty());
+        assert!(!presence.activities[0].extra.is_empty());
+
+        let stripped = presence.clone_stripped();
+        assert!(stripped.extra.is_empty());
+        assert!(stripped.activities[0].extra.is_empty());
+    }
+
+    #[test]
+    fn test_presence_origin() {
+        let presence = |guild_id| -> Presence {
+            json::from_value(json!({
+                "user": {"id": "1"},
+                "status": "online",
+                "guild_id": guild_id,
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(presence(json!("2")).origin(), PresenceOrigin::Guild(GuildId(2)));
+        assert_eq!(presence(json!(null)).origin(), PresenceOrigin::Global);
+    }
+
+    #[test]
+    #[cfg(feature = "presence_extra_fields")]
+    fn test_presence_extra_fields() {
+        let value = json!({
+            "user": {"id": "1"},
+            "status": "online",
+            "processed_at_timestamp": 1_650_000_000_000_u64,
+            "activities": [{
+                "type": 0,
+                "name": "Game",
+                "platform": "desktop",
+                "supported_platforms": ["desktop", "xbox"],
+            }],
+        });
+
+        let presence: Presence = json::from_value(value.clone()).unwrap();
+        assert_eq!(presence.extra.len(), 1);
+        assert_eq!(presence.extra["processed_at_timestamp"], json!(1_650_000_000_000_u64));
+
+        let activity = &presence.activities[0];
+        assert_eq!(activity.name, "Game");
+        assert_eq!(activity.extra.len(), 2);
+        assert_eq!(activity.extra["platform"], json!("desktop"));
+
+        let serialized = json::to_value(&presence).unwrap();
+        assert_eq!(serialized["processed_at_timestamp"], value["processed_at_timestamp"]);
+        assert_eq!(
+            serialized["activities"][0]["supported_platforms"],
+            value["activities"][0]["supported_platforms"]
+        );
+
+        let roundtripped: Presence = json::from_value(serialized).unwrap();
+        assert_eq!(roundtripped.extra, presence.extra);
+        assert_eq!(roundtripped.activities[0].extra, activity.extra);
+
+        // Unknown fields are not sent back to the gateway.
+        let outbound = json::to_value(OutboundPresence::from(presence)).unwrap();
+        assert_eq!(outbound["activities"][0]["name"], json!("Game"));
+        assert_eq!(outbound["activities"][0].get("platform"), None);
+    }
+
+    #[test]
+    fn test_presence_dedup_activities() {
+        let value = json!({
+            "user": {"id": "1"},
+            "status": "online",
+            "activities": [
+                {"type": 4, "name": "Custom Status", "state": "hello"},
+                {"type": 0, "name": "Game"},
+                {"type": 4, "name": "Custom Status", "state": "hello"},
+                {"type": 4, "name": "Custom Status", "state": "goodbye"},
+                {"type": 0, "name": "Game"},
+            ],
+        });
+
+        let mut presence: Presence = json::from_value(value).unwrap();
+        assert_eq!(presence.activities.len(), 5);
+
+        presence.dedup_activities();
+
+        let activities: Vec<_> = presence
+            .activities
+            .iter()
+            .map(|a| (a.kind, a.name.as_str(), a.state.as_deref()))
+            .collect();
+        assert_eq!(activities, vec![
+            (ActivityType::Custom, "Custom Status", Some("hello")),
+            (ActivityType::Playing, "Game", None),
+            (ActivityType::Custom, "Custom Status", Some("goodbye")),
+        ]);
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_is_joinable() {
+        let activity = |flags: u64, size: json::Value| -> Activity {
+            json::from_value(json!({
+                "type": 0,
+                "name": "Game",
+                "flags": flags,
+                "party": {"id": "party", "size": size},
+            }))
+            .unwrap()
+        };
+
+        assert!(activity(2, json!([1, 4])).is_joinable());
+        assert!(activity(2, json!(null)).is_joinable());
+        assert!(!activity(2, json!([4, 4])).is_joinable());
+        assert!(!activity(0, json!([1, 4])).is_joinable());
+        assert!(!Activity::playing("Game").is_joinable());
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_party_free_slots() {
+        let party = |size: json::Value| -> ActivityParty {
+            json::from_value(json!({"id": "party", "size": size})).unwrap()
+        };
+
+        // Empty.
+        assert_eq!(party(json!([0, 4])).is_full(), Some(false));
+        assert_eq!(party(json!([0, 4])).free_slots(), Some(4));
+        // Full.
+        assert_eq!(party(json!([4, 4])).is_full(), Some(true));
+        assert_eq!(party(json!([4, 4])).free_slots(), Some(0));
+        // Reported above its maximum.
+        assert_eq!(party(json!([5, 4])).is_full(), Some(true));
+        assert_eq!(party(json!([5, 4])).free_slots(), Some(0));
+        // Of unknown size.
+        assert_eq!(party(json!(null)).is_full(), None);
+        assert_eq!(party(json!(null)).free_slots(), None);
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_matches_filter() {
+        let mut game = Activity::playing("Some Game");
+        game.application_id = Some(ApplicationId(1));
+
+        assert!(game.matches_filter(&ActivityFilter::new()));
+        assert!(game.matches_filter(&ActivityFilter::new().kind(ActivityType::Playing)));
+        assert!(!game.matches_filter(&ActivityFilter::new().kind(ActivityType::Watching)));
+        assert!(game.matches_filter(&ActivityFilter::new().name_contains("Game")));
+        assert!(!game.matches_filter(&ActivityFilter::new().name_contains("game")));
+        assert!(game.matches_filter(&ActivityFilter::new().name_contains_ci("SOME game")));
+        assert!(game.matches_filter(&ActivityFilter::new().application_id(1)));
+        assert!(!game.matches_filter(&ActivityFilter::new().application_id(2)));
+        assert!(!game.matches_filter(&ActivityFilter::new().requires_streaming(true)));
+
+        let filter = ActivityFilter::new().kind(ActivityType::Playing).name_contains("Other");
+        assert!(!game.matches_filter(&filter));
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_streaming_platform() {
+        let platform = |url: &str| Activity::streaming("Stream", url).streaming_platform();
+
+        assert_eq!(platform("https://www.twitch.tv/someone"), Some(StreamingPlatform::Twitch));
+        assert_eq!(platform("https://youtu.be/dQw4w9WgXcQ"), Some(StreamingPlatform::YouTube));
+        assert_eq!(platform("https://m.youtube.com/watch"), Some(StreamingPlatform::YouTube));
+        assert_eq!(platform("https://fb.gg/someone"), Some(StreamingPlatform::Facebook));
+        assert_eq!(
+            platform("https://nottwitch.tv/someone"),
+            Some(StreamingPlatform::Other("nottwitch.tv".to_string()))
+        );
+
+        assert_eq!(Activity::playing("Game").streaming_platform(), None);
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_music_source() {
+        let spotify: Activity =
+            json::from_value(json!({"type": 2, "name": "Spotify", "state": "Artist"})).unwrap();
+        assert_eq!(spotify.music_source(), Some(MusicSource::Spotify));
+
+        let impostor: Activity = json::from_value(json!({
+            "type": 2,
+            "name": "Spotify",
+            "application_id": "1",
+        }))
+        .unwrap();
+        assert_eq!(impostor.music_source(), Some(MusicSource::Other));
+
+        let by_assets: Activity = json::from_value(json!({
+            "type": 2,
+            "name": "Music",
+            "application_id": "1",
+            "assets": {"large_image": "spotify:ab67616d"},
+        }))
+        .unwrap();
+        assert_eq!(by_assets.music_source(), Some(MusicSource::Spotify));
+
+        assert_eq!(
+            Activity::listening("Apple Music").music_source(),
+            Some(MusicSource::AppleMusic)
+        );
+        assert_eq!(Activity::playing("Spotify").music_source(), None);
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_merge() {
+        let mut activity: Activity = json::from_value(json!({
+            "type": 2,
+            "name": "Spotify",
+            "details": "Old song",
+            "state": "Artist",
+            "timestamps": {"start": 1000, "end": 2000},
+            "assets": {"large_image": "spotify:ab67616d"},
+        }))
+        .unwrap();
+
+        let mut update = Activity::new(String::new(), ActivityType::Playing);
+        update.details = Some("New song".to_string());
+        activity.merge(update);
+
+        assert_eq!(activity.kind, ActivityType::Listening);
+        assert_eq!(activity.name, "Spotify");
+        assert_eq!(activity.details.as_deref(), Some("New song"));
+        assert_eq!(activity.state.as_deref(), Some("Artist"));
+        assert_eq!(activity.timestamps.as_ref().and_then(|t| t.start), Some(1000));
+        assert!(activity.assets.is_some());
+    }
+
+    #[cfg(all(feature = "model", feature = "builder"))]
+    #[test]
+    fn test_presence_summary_embed() {
+        let presence: Presence = json::from_value(json!({
+            "user": {"id": "1", "username": "someone"},
+            "status": "dnd",
+            "client_status": {"desktop": "dnd", "mobile": "idle"},
+            "activities": [
+                {"type": 2, "name": "Spotify", "details": "Song", "state": "Artist"},
+                {"type": 4, "name": "Custom Status"},
+            ],
+        }))
+        .unwrap();
+
+        let embed = json::hashmap_to_json_map(presence.summary_embed().0);
+        assert_eq!(embed["title"], "someone");
+        assert_eq!(embed["color"], OnlineStatus::DoNotDisturb.color());
+        assert_eq!(embed["fields"][0]["name"], "Listening to Spotify");
+        assert_eq!(embed["fields"][0]["value"], "Song\nArtist");
+        assert_eq!(embed["fields"][1]["value"], "\u{200B}");
+        assert_eq!(embed["footer"]["text"], "Active on Desktop, Mobile");
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_session_start_limit_throttle_start() {
+        let limit: SessionStartLimit = json::from_value(json!({
+            "remaining": 2,
+            "reset_after": 5000,
+            "total": 1000,
+            "max_concurrency": 1,
+        }))
+        .unwrap();
+
+        assert!(limit.can_start(2));
+        assert_eq!(limit.throttle_start(2), None);
+        assert!(!limit.can_start(3));
+        assert_eq!(limit.throttle_start(3), Some(std::time::Duration::from_secs(5)));
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_bot_gateway_can_identify_now() {
+        let gateway = |remaining| -> BotGateway {
+            json::from_value(json!({
+                "url": "wss://gateway.discord.gg",
+                "shards": 1,
+                "session_start_limit": {
+                    "remaining": remaining,
+                    "reset_after": 5000,
+                    "total": 1000,
+                    "max_concurrency": 1,
+                },
+            }))
+            .unwrap()
+        };
+
+        assert!(gateway(1).can_identify_now());
+        assert_eq!(gateway(1).will_be_able_to_identify_after(), Duration::from_secs(0));
+        assert!(!gateway(0).can_identify_now());
+        assert_eq!(gateway(0).will_be_able_to_identify_after(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_session_start_limit_camel_case() {
+        // As rewritten by some gateway proxies.
+        let limit: SessionStartLimit = json::from_value(json!({
+            "remaining": 999,
+            "resetAfter": 5000,
+            "total": 1000,
+            "maxConcurrency": 16,
+        }))
+        .unwrap();
+
+        assert_eq!(limit.reset_after, 5000);
+        assert_eq!(limit.max_concurrency, 16);
+    }
+
+    #[test]
+    fn test_activity_from_discord_activity_update() {
+        // As sent by Discord in a PRESENCE_UPDATE.
+        let value = json!({
+            "type": 2,
+            "timestamps": {"start": 1_670_000_000_000_u64, "end": 1_670_000_200_000_u64},
+            "sync_id": "4cOdK2wGLETKBW3PvgPWqT",
+            "state": "Rick Astley",
+            "session_id": "2e7a2d2bb8a5b1f5b2f3a4e9c2b2c1d0",
+            "party": {"id": "spotify:123456789012345678"},
+            "name": "Spotify",
+            "id": "spotify:1",
+            "flags": 48,
+            "details": "Never Gonna Give You Up",
+            "created_at": 1_670_000_001_000_u64,
+            "assets": {
+                "large_text": "Whenever You Need Somebody",
+                "large_image": "spotify:ab67616d0000b27315ebbedaacef61af244262a8",
+            },
+        });
+
+        let activity = Activity::from_discord_activity_update(&value).unwrap();
+        assert_eq!(activity.kind, ActivityType::Listening);
+        assert_eq!(activity.name, "Spotify");
+        assert_eq!(activity.details.as_deref(), Some("Never Gonna Give You Up"));
+        assert_eq!(activity.state.as_deref(), Some("Rick Astley"));
+        assert_eq!(activity.flags, Some(ActivityFlags::SYNC | ActivityFlags::PLAY));
+        assert_eq!(activity.created_at.unwrap().unix_millis(), 1_670_000_001_000);
+
+        let timestamps = activity.timestamps.unwrap();
+        assert_eq!(timestamps.end, Some(1_670_000_200_000));
+        assert_eq!(timestamps.end_time().unwrap().unix_timestamp(), 1_670_000_200);
+
+        assert!(Activity::from_discord_activity_update(&json!({"type": 2})).is_err());
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_emoji_same_emoji() {
+        let emoji = |name: &str, id: Option<u64>, animated: Option<bool>| ActivityEmoji {
+            name: name.to_string(),
+            id: id.map(EmojiId),
+            animated,
+        };
+
+        assert!(emoji("blob", Some(1), Some(true)).same_emoji(&emoji("blob", Some(1), None)));
+        assert!(!emoji("blob", Some(1), None).same_emoji(&emoji("blob", Some(2), None)));
+        assert!(emoji("\u{1F44D}", None, None).same_emoji(&emoji("\u{1F44D}", None, Some(false))));
+        assert!(!emoji("\u{1F44D}", None, None).same_emoji(&emoji("\u{1F44E}", None, None)));
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_emoji_to_mention() {
+        let emoji = |name: &str, id: Option<u64>, animated: Option<bool>| ActivityEmoji {
+            name: name.to_string(),
+            id: id.map(EmojiId),
+            animated,
+        };
+
+        assert_eq!(emoji("blob", Some(1), None).to_mention().as_deref(), Some("<:blob:1>"));
+        assert_eq!(emoji("blob", Some(1), Some(true)).to_mention().as_deref(), Some("<a:blob:1>"));
+        assert_eq!(emoji("\u{1F44D}", None, None).to_mention().as_deref(), Some("\u{1F44D}"));
+        assert_eq!(emoji("", Some(1), None).to_mention(), None);
+    }
+
+    #[test]
+    fn test_client_status_effective_status() {
+        use OnlineStatus::*;
+
+        let client_status = |desktop, mobile, web| ClientStatus {
+            desktop,
+            mobile,
+            web,
+        };
+
+        assert_eq!(client_status(None, None, None).effective_status(), Offline);
+        assert_eq!(client_status(Some(Idle), None, None).effective_status(), Idle);
+        assert_eq!(client_status(Some(Idle), Some(Online), None).effective_status(), Online);
+        assert_eq!(
+            client_status(Some(Online), Some(Idle), Some(DoNotDisturb)).effective_status(),
+            DoNotDisturb
+        );
+        assert_eq!(client_status(Some(Offline), None, Some(Idle)).effective_status(), Idle);
+    }
+
+    #[test]
+    fn test_client_status_dominant_status() {
+        use OnlineStatus::*;
+
+        let client_status = |desktop, mobile, web| ClientStatus {
+            desktop,
+            mobile,
+            web,
+        };
+
+        assert_eq!(client_status(None, None, None).dominant_status(), None);
+
+        // Ordered from most to least visible.
+        let ranked = [Online, Idle, DoNotDisturb, Offline];
+        for (i, &high) in ranked.iter().enumerate() {
+            assert_eq!(client_status(Some(high), None, None).dominant_status(), Some(high));
+
+            for &low in &ranked[i..] {
+                for &other in &ranked[i..] {
+                    let expected = Some(high);
+                    let statuses = [
+                        client_status(Some(high), Some(low), None),
+                        client_status(None, Some(low), Some(high)),
+                        client_status(Some(low), Some(high), Some(other)),
+                        client_status(Some(other), Some(low), Some(high)),
+                    ];
+
+                    for status in &statuses {
+                        assert_eq!(status.dominant_status(), expected, "{:?}", status);
+                    }
+                }
+            }
+        }
+
+        let presence: Presence = json::from_value(json!({
+            "user": {"id": "1"},
+            "status": "idle",
+            "client_status": {"desktop": "idle", "mobile": "online"},
+        }))
+        .unwrap();
+        assert_eq!(presence.primary_status(), Online);
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_rich_embed_fields() {
+        assert!(Activity::playing("Game").to_rich_embed_fields().is_empty());
+
+        let activity: Activity = json::from_value(json!({
+            "type": 1,
+            "name": "Stream",
+            "details": "Speedrun",
+            "party": {"size": [2, 4]},
+            "timestamps": {"start": 1_000_000},
+            "url": "https://twitch.tv/someone",
+        }))
+        .unwrap();
+
+        let field = |name: &str, value: &str, inline| (name.to_string(), value.to_string(), inline);
+        assert_eq!(activity.rich_embed_fields_at(1_000_000 + 3_723_000), vec![
+            field("Details", "Speedrun", false),
+            field("Party", "2/4", true),
+            field("Elapsed", "1:02:03", true),
+            field("Stream", "https://twitch.tv/someone", false),
+        ]);
+        assert_eq!(
+            activity.rich_embed_fields_at(1_000_000 + 65_000)[2],
+            field("Elapsed", "01:05", true)
+        );
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_elapsed_time_display() {
+        let mut activity = Activity::playing("Game");
+        assert_eq!(activity.elapsed_time_display(), None);
+
+        activity.timestamps = Some(ActivityTimestamps::new(None, Some(1_000_000)));
+        assert_eq!(activity.elapsed_time_display(), None);
+
+        activity.timestamps = Some(ActivityTimestamps::new(Some(1_000_000), None));
+        let display = |elapsed: u64| activity.elapsed_time_display_at(1_000_000 + elapsed);
+        assert_eq!(display(65_000).as_deref(), Some("1m 5s"));
+        assert_eq!(display(3_723_000).as_deref(), Some("1h 2m 3s"));
+        assert_eq!(display(3_605_000).as_deref(), Some("1h 5s"));
+        assert_eq!(display(999).as_deref(), Some("0s"));
+        assert_eq!(activity.elapsed_time_display_at(0).as_deref(), Some("0s"));
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_validate_flags() {
+        let mut activity = Activity::listening("Spotify");
+        activity.flags = Some(ActivityFlags::default_for(ActivityType::Listening));
+        assert!(activity.validate_flags().is_ok());
+
+        activity.kind = ActivityType::Custom;
+        assert_eq!(
+            activity.validate_flags(),
+            Err(ActivityError::InvalidFlags {
+                kind: ActivityType::Custom,
+                flags: ActivityFlags::SYNC | ActivityFlags::PLAY,
+            })
+        );
+
+        activity.kind = ActivityType::Playing;
+        assert_eq!(
+            activity.validate_flags(),
+            Err(ActivityError::InvalidFlags {
+                kind: ActivityType::Playing,
+                flags: ActivityFlags::SYNC,
+            })
+        );
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_with_emoji() {
+        let custom = Activity::new("Custom Status".to_string(), ActivityType::Custom)
+            .with_state("Busy")
+            .with_emoji_unicode("\u{1F3AE}");
+        let emoji = custom.emoji.unwrap();
+        assert_eq!(emoji.name, "\u{1F3AE}");
+        assert_eq!(emoji.id, None);
+
+        let custom = Activity::new("Custom Status".to_string(), ActivityType::Custom).with_emoji(
+            ActivityEmoji {
+                name: "blob".to_string(),
+                id: Some(EmojiId(1)),
+                animated: Some(true),
+            },
+        );
+        assert_eq!(custom.emoji.unwrap().id, Some(EmojiId(1)));
+
+        assert!(Activity::playing("Game").with_emoji_unicode("\u{1F3AE}").emoji.is_none());
+    }
+
+    #[cfg(feature = "utils")]
+    #[test]
+    fn test_activity_type_accent_colour() {
+        use crate::utils::Colour;
+
+        assert_eq!(ActivityType::Listening.accent_colour(), Colour(0x1DB954));
+        assert_eq!(ActivityType::Custom.accent_colour(), ActivityType::Unknown.accent_colour());
+        assert_ne!(ActivityType::Playing.accent_colour(), ActivityType::Custom.accent_colour());
+    }
+
+    #[test]
+    fn test_activity_button_label_over_limit() {
+        let label = "\u{E9}".repeat(40);
+        let max_label = "\u{E9}".repeat(32);
+        let mut activity: Activity = json::from_value(json!({
+            "type": 0,
+            "name": "Game",
+            "buttons": [label, max_label],
+        }))
+        .unwrap();
+        assert_eq!(activity.buttons[0].label, label);
+        assert!(activity.validate_buttons().is_err());
+
+        activity.truncate_button_labels();
+        assert_eq!(activity.buttons[0].label, format!("{}\u{2026}", "\u{E9}".repeat(31)));
+        assert_eq!(activity.buttons[1].label, max_label);
+        assert!(activity.validate_buttons().is_ok());
+    }
+
+    #[test]
+    fn test_activity_with_buttons() {
+        let button = |label| ActivityButton::new(label, "https://example.com");
+
+        let activity =
+            Activity::playing("Game").with_buttons(vec![button("Join"), button("Watch")]).unwrap();
+        assert_eq!(activity.buttons.len(), 2);
+
+        assert_eq!(
+            Activity::playing("Game")
+                .with_buttons(vec![button("Join"), button("Watch"), button("Spectate")])
+                .unwrap_err(),
+            ActivityError::TooManyButtons(3)
+        );
+        let long_label = "a".repeat(33);
+        assert_eq!(
+            Activity::playing("Game").with_buttons(vec![button(&long_label)]).unwrap_err(),
+            ActivityError::ButtonLabelTooLong(33)
+        );
+
+        let mut presence = OutboundPresence {
+            since: None,
+            activities: vec![activity],
+            status: OnlineStatus::Online,
+            afk: false,
+        };
+        assert!(presence.validate().is_ok());
+
+        presence.activities[0].buttons.push(button("Spectate"));
+        assert_eq!(presence.validate(), Err(ActivityError::TooManyButtons(3)));
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_outbound_presence_activity_order() {
+        let names = |presence: &OutboundPresence| -> Vec<Value> {
+            let value = json::to_value(presence).unwrap();
+            value["activities"].as_array().unwrap().iter().map(|a| a["name"].clone()).collect()
+        };
+
+        let presence = OutboundPresence {
+            since: None,
+            activities: vec![
+                Activity::playing("Game"),
+                Activity::listening("Music"),
+                Activity::watching("Video"),
+            ],
+            status: OnlineStatus::Online,
+            afk: false,
+        };
+        assert_eq!(names(&presence), [json!("Game"), json!("Music"), json!("Video")]);
+
+        let presence = presence.with_primary(Activity::watching("Video"));
+        assert_eq!(names(&presence), [json!("Video"), json!("Game"), json!("Music")]);
+
+        let presence = presence.with_primary(Activity::competing("Tournament"));
+        assert_eq!(names(&presence), [
+            json!("Tournament"),
+            json!("Video"),
+            json!("Game"),
+            json!("Music")
+        ]);
+    }
+
+    #[test]
+    fn test_activity_flags_names_round_trip() {
+        #[derive(Deserialize, Serialize)]
+        struct Stored {
+            #[serde(with = "activity_flags::names")]
+            flags: ActivityFlags,
+        }
+
+        let stored = Stored {
+            flags: ActivityFlags::SYNC | ActivityFlags::PLAY | ActivityFlags::INSTANCE,
+        };
+
+        let value = json::to_value(&stored).unwrap();
+        assert_eq!(value, json!({"flags": ["INSTANCE", "PLAY", "SYNC"]}));
+
+        let stored: Stored = json::from_value(value).unwrap();
+        assert_eq!(
+            stored.flags,
+            ActivityFlags::SYNC | ActivityFlags::PLAY | ActivityFlags::INSTANCE
+        );
+
+        assert!(json::from_value::<Stored>(json!({"flags": ["NOT_A_FLAG"]})).is_err());
+    }
+
+    #[cfg(feature = "utils")]
+    #[test]
+    fn test_ready_owns_guild() {
+        let ready = |shard| -> Ready {
+            let mut value = ready_json();
+            value["shard"] = shard;
+
+            json::from_value(value).unwrap()
+        };
+
+        let guild_id = GuildId(81384788765712384);
+        assert_eq!(Ready::shard_for_guild(guild_id, 17), 7);
+
+        assert!(ready(json!([7, 17])).owns_guild(guild_id));
+        assert!(!ready(json!([6, 17])).owns_guild(guild_id));
+        assert!(ready(json!(null)).owns_guild(guild_id));
+    }
+
+    #[test]
+    fn test_ready_private_channel_and_presence_ids() {
+        let mut value = ready_json();
+        value["presences"] = json!([{"user": {"id": "3"}, "status": "online"}]);
+        value["private_channels"] = json!([{
+            "id": "4",
+            "type": 1,
+            "recipients": [{"id": "3", "username": "friend", "discriminator": "0001"}],
+        }]);
+        let ready: Ready = json::from_value(value).unwrap();
+
+        assert_eq!(ready.private_channel_ids(), vec![ChannelId(4)]);
+        assert_eq!(ready.private_channel_count(), 1);
+        assert_eq!(ready.presence_user_ids(), vec![UserId(3)]);
+        assert_eq!(ready.presence_count(), 1);
+    }
+
+    #[test]
+    fn test_ready_resume_gateway_url() {
+        let ready = |url| -> Ready {
+            let mut value = ready_json();
+            value["resume_gateway_url"] = url;
+
+            json::from_value(value).unwrap()
+        };
+
+        assert_eq!(ready(json!(null)).resume_gateway_url, None);
+
+        let url = "wss://gateway-us-east1-b.discord.gg";
+        assert_eq!(ready(json!(url)).resume_gateway_url.as_deref(), Some(url));
+    }
+
+    #[test]
+    fn test_presence_user_tag() {
+        let mut user = PresenceUser {
+            name: Some("name".to_string()),
+            discriminator: Some(42),
+            ..Default::default()
+        };
+        assert_eq!(user.discriminator_display(), "#0042");
+        assert_eq!(user.tag().as_deref(), Some("name#0042"));
+
+        user.discriminator = Some(0);
+        assert_eq!(user.discriminator_display(), "");
+        assert_eq!(user.tag().as_deref(), Some("name"));
+
+        user.discriminator = None;
+        assert_eq!(user.tag().as_deref(), Some("name"));
+
+        user.name = None;
+        assert_eq!(user.tag(), None);
+    }
+
+    #[test]
+    fn test_presence_user_update_fields() {
+        let mut user = PresenceUser::default();
+
+        assert_eq!(user.update_username("old".to_string()), None);
+        assert_eq!(user.update_username("new".to_string()).as_deref(), Some("old"));
+        assert_eq!(user.name.as_deref(), Some("new"));
+
+        assert_eq!(user.update_avatar_hash("a".to_string()), None);
+        assert_eq!(user.update_discriminator(1), None);
+        assert_eq!(user.update_discriminator(2), Some(1));
+        assert_eq!(user.update_public_flags(UserPublicFlags::DISCORD_EMPLOYEE), None);
+        assert_eq!(
+            user.update_public_flags(UserPublicFlags::PARTNERED_SERVER_OWNER),
+            Some(UserPublicFlags::DISCORD_EMPLOYEE)
+        );
+    }
+
+    #[test]
+    fn test_presence_changed_from() {
+        let old: Presence = json::from_value(json!({
+            "user": {"id": "1", "username": "user"},
+            "status": "online",
+            "client_status": {"desktop": "online"},
+            "activities": [{"type": 0, "name": "Game", "timestamps": {"start": 1}}],
+        }))
+        .unwrap();
+
+        let new: Presence = json::from_value(json!({
+            "user": {"id": "1"},
+            "status": "online",
+            "client_status": {"desktop": "online"},
+            "activities": [{"type": 0, "name": "Game", "timestamps": {"start": 2}}],
+        }))
+        .unwrap();
+        assert!(!new.changed_from(&old).any());
+
+        let new: Presence = json::from_value(json!({
+            "user": {"id": "1", "username": "renamed"},
+            "status": "idle",
+            "client_status": {"desktop": "idle"},
+            "activities": [],
+        }))
+        .unwrap();
+        let change = new.changed_from(&old);
+        assert!(change.status_changed);
+        assert!(change.activities_changed);
+        assert!(change.platform_changed);
+        assert!(change.user_data_changed);
+        assert!(change.went_live.is_none());
+        assert!(!change.stopped_streaming);
+    }
+
+    #[test]
+    fn test_presence_is_afk() {
+        let presence = |status, since| -> Presence {
+            json::from_value(json!({
+                "user": {"id": "1"},
+                "status": status,
+                "since": since,
+            }))
+            .unwrap()
+        };
+
+        let afk = presence("idle", json!(1_650_000_000_000_u64));
+        assert!(afk.is_afk());
+        assert_eq!(
+            afk.afk_since(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_650_000_000))
+        );
+
+        let idle = presence("idle", json!(null));
+        assert!(!idle.is_afk());
+        assert_eq!(idle.afk_since(), None);
+
+        assert!(!presence("online", json!(1_650_000_000_000_u64)).is_afk());
+    }
+
+    #[test]
+    fn test_presence_changed_from_streaming() {
+        let presence = |activities| -> Presence {
+            json::from_value(json!({
+                "user": {"id": "1"},
+                "status": "online",
+                "activities": activities,
+            }))
+            .unwrap()
+        };
+        let stream = |name, url| json!({"type": 1, "name": name, "url": url});
+
+        let offline = presence(json!([]));
+        let live = presence(json!([stream("Speedrun", "https://twitch.tv/someone")]));
+        let renamed = presence(json!([stream("Chatting", "https://twitch.tv/someone")]));
+        let other = presence(json!([stream("Chatting", "https://twitch.tv/other")]));
+
+        let change = live.changed_from(&offline);
+        assert_eq!(change.went_live.map(|a| a.name.as_str()), Some("Speedrun"));
+        assert!(!change.stopped_streaming);
+
+        let change = renamed.changed_from(&live);
+        assert!(change.went_live.is_none());
+        assert!(!change.stopped_streaming);
+
+        let change = offline.changed_from(&renamed);
+        assert!(change.went_live.is_none());
+        assert!(change.stopped_streaming);
+
+        let change = other.changed_from(&renamed);
+        assert_eq!(change.went_live.map(|a| a.name.as_str()), Some("Chatting"));
+        assert!(change.stopped_streaming);
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_name_truncated() {
+        let activity = Activity::playing("h\u{E9}llo w\u{F6}rld");
+
+        assert_eq!(activity.name_truncated(5), "h\u{E9}llo\u{2026}");
+        assert_eq!(activity.name_truncated(11), "h\u{E9}llo w\u{F6}rld");
+        assert_eq!(activity.name_truncated(0), "\u{2026}");
+        assert_eq!(activity.details_truncated(5), None);
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_assets_external_image() {
+        let assets = ActivityAssets::external_image("https://i.scdn.co/image/ab67616d").unwrap();
+        assert_eq!(
+            assets.large_image.as_deref(),
+            Some("mp:external/https/i.scdn.co/image/ab67616d")
+        );
+
+        assert!(ActivityAssets::external_image("ftp://example.com/a.png").is_err());
+        assert!(ActivityAssets::external_image("not a url").is_err());
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_secrets_sanitize() {
+        assert!(ActivitySecrets::generate().is_valid());
+
+        let secrets = ActivitySecrets {
+            join: Some("server:1/room 2".to_string()),
+            match_: None,
+            spectate: Some("a-b_c.D9".to_string()),
+        };
+        assert!(!secrets.is_valid());
+
+        let sanitized = secrets.sanitize();
+        assert!(sanitized.is_valid());
+        assert_eq!(sanitized.join.as_deref(), Some("server_1_room_2"));
+        assert_eq!(sanitized.match_, None);
+        assert_eq!(sanitized.spectate.as_deref(), Some("a-b_c.D9"));
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_secrets_generate() {
+        let secrets = ActivitySecrets::generate();
+        // 16 bytes encode to 22 characters of base64 without padding.
+        assert!(secrets.secrets().all(|secret| secret.len() == 22));
+        assert_eq!(secrets.secrets().count(), 3);
+        assert_ne!(secrets.join, secrets.match_);
+        assert_ne!(ActivitySecrets::generate().join, secrets.join);
+
+        let join = ActivitySecrets::generate_join_only();
+        assert!(join.join.is_some());
+        assert!(join.match_.is_none());
+        assert!(join.spectate.is_none());
+
+        let spectate = ActivitySecrets::generate_spectate_only();
+        assert!(spectate.join.is_none());
+        assert!(spectate.match_.is_none());
+        assert!(spectate.spectate.is_some());
+    }
+
+    #[cfg(feature = "model")]
+    #[test]
+    fn test_activity_assets_builder() {
+        let assets = ActivityAssets::builder()
+            .large_image("1")
+            .large_text("Large")
+            .small_image("2")
+            .small_text("\u{E9}".repeat(128))
+            .build()
+            .unwrap();
+        assert_eq!(assets.large_image.as_deref(), Some("1"));
+        assert_eq!(assets.large_text.as_deref(), Some("Large"));
+        assert_eq!(assets.small_image.as_deref(), Some("2"));
+        assert_eq!(assets.image_keys(), (Some("1"), Some("2")));
+        assert_eq!(ActivityAssets::builder().build().unwrap().image_keys(), (None, None));
+
+        assert_eq!(
+            ActivityAssets::builder().large_text("a".repeat(129)).build().unwrap_err(),
+            ActivityError::AssetTextTooLong(129)
+        );
+    }
+
+    fn assert_presence_round_trip(value: json::Value) {
+        let presence: Presence = json::from_value(value).unwrap();
+        let serialized = json::to_value(&presence).unwrap();
+
+        let reloaded: Presence = json::from_value(serialized.clone()).unwrap();
+        assert_eq!(json::to_value(&reloaded).unwrap(), serialized);
+    }
+
+    #[test]
+    fn test_presence_round_trip_all_fields() {
+        assert_presence_round_trip(json!({
+            "user": {
+                "id": "1",
+                "avatar": "a_0123456789abcdef",
+                "bot": false,
+                "discriminator": "0042",
+                "email": "user@example.com",
+                "mfa_enabled": true,
+                "username": "user",
+                "verified": true,
+                "public_flags": 64,
+            },
+            "status": "idle",
+            "guild_id": "2",
+            "client_status": {"desktop": "idle", "mobile": "online", "web": "dnd"},
+            "activities": [
+                {
+                    "type": 4,
+                    "name": "Custom Status",
+                    "state": "hello",
+                    "emoji": {"name": "wave", "id": "3", "animated": true},
+                },
+                {
+                    "type": 0,
+                    "name": "Game",
+                    "application_id": "4",
+                    "details": "In a match",
+                    "state": "Ranked",
+                    "flags": 3,
+                    "instance": true,
+                    "assets": {
+                        "large_image": "5",
+                        "large_text": "Map",
+                        "small_image": "6",
+                        "small_text": "Rank",
+                    },
+                    "party": {"id": "party", "size": [1, 4]},
+                    "secrets": {"join": "join", "match": "match", "spectate": "spectate"},
+                    "timestamps": {"start": 1, "end": 2},
+                    "buttons": ["Join", "Watch"],
+                },
+                {"type": 1, "name": "Stream", "url": "https://twitch.tv/user"},
+            ],
+        }));
+    }
+
+    #[test]
+    fn test_presence_round_trip_no_optional_fields() {
+        assert_presence_round_trip(json!({
+            "user": {"id": "1"},
+            "status": "offline",
+        }));
+    }
+
+    #[cfg(feature = "compact_presence")]
+    #[test]
+    fn test_presence_compact_bytes_round_trip() {
+        let presence: Presence = json::from_value(json!({
+            "user": {"id": "1", "username": "user", "discriminator": "0001"},
+            "status": "idle",
+            "guild_id": "2",
+            "client_status": {"desktop": "idle", "mobile": "online"},
+            "activities": [{
+                "type": 2,
+                "name": "Spotify",
+                "details": "Song",
+                "state": "Artist",
+                "created_at": 1_600_000_000_000_u64,
+                "timestamps": {"start": 1_600_000_000_000_u64},
+                "buttons": ["Listen"],
+            }],
+        }))
+        .unwrap();
+
+        let bytes = presence.to_compact_bytes().unwrap();
+        assert!(bytes.len() < json::to_string(&presence).unwrap().len());
+
+        let decoded = Presence::from_compact_bytes(&bytes).unwrap();
+        assert_eq!(json::to_value(&decoded).unwrap(), json::to_value(&presence).unwrap());
+    }
+
+    // The unstable `sync_id` and `session_id` fields would be part of the output.
+    #[cfg(not(feature = "unstable_discord_api"))]
+    #[test]
+    fn test_presence_update_payload_shape() {
+        let presence: Presence = json::from_value(json!({
+            "user": {"id": "1"},
+            "status": "dnd",
+            "guild_id": "2",
+            "client_status": {"desktop": "dnd"},
+            "activities": [{"type": 0, "name": "Game"}],
+        }))
+        .unwrap();
+
+        let payload = PresenceUpdatePayload::new(presence.to_outbound(true));
+
+        assert_eq!(
+            json::to_value(&payload).unwrap(),
+            json!({
+                "op": 3,
+                "d": {
+                    "since": null,
+                    "activities": [{
+                        "application_id": null,
+                        "assets": null,
+                        "details": null,
+                        "flags": null,
+                        "instance": null,
+                        "type": 0,
+                        "name": "Game",
+                        "party": null,
+                        "secrets": null,
+                        "state": null,
+                        "emoji": null,
+                        "timestamps": null,
+                        "url": null,
+                        "buttons": [],
+                    }],
+                    "status": "dnd",
+                    "afk": true,
+                },
+            })
+        );
+    }
+}
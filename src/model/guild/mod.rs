@@ -3235,6 +3235,38 @@ enum_number!(NsfwLevel {
     AgeRestricted
 });
 
+/// The JSON of a minimal [`Guild`] owned by user 10, for tests to add fields
+/// to.
+#[cfg(test)]
+pub(crate) fn guild_json(guild_id: u64) -> Value {
+    crate::json::json!({
+        "id": guild_id.to_string(),
+        "name": "guild",
+        "owner_id": "10",
+        "joined_at": "2022-01-01T00:00:00Z",
+        "afk_timeout": 0,
+        "default_message_notifications": 0,
+        "explicit_content_filter": 0,
+        "features": [],
+        "large": false,
+        "member_count": 0,
+        "mfa_level": 0,
+        "roles": [],
+        "verification_level": 0,
+        "premium_tier": 0,
+        "nsfw_level": 0,
+        "preferred_locale": "en-US",
+        "system_channel_flags": 0,
+        "emojis": [],
+        "stickers": [],
+        "channels": [],
+        "members": [],
+        "presences": [],
+        "voice_states": [],
+        "threads": [],
+    })
+}
+
 #[cfg(test)]
 mod test {
     #[cfg(feature = "model")]
@@ -85,6 +85,21 @@ cfg_if::cfg_if! {
                 self.0.timestamp()
             }
 
+            /// Create a new `Timestamp` from a UNIX timestamp in milliseconds.
+            ///
+            /// # Errors
+            ///
+            /// Returns `Err` if the value is invalid.
+            pub fn from_unix_millis(millis: i64) -> Result<Self, InvalidTimestamp> {
+                Utc.timestamp_millis_opt(millis).single().map(Self).ok_or(InvalidTimestamp)
+            }
+
+            /// Returns the number of non-leap milliseconds since January 1, 1970 0:00:00 UTC
+            #[must_use]
+            pub fn unix_millis(&self) -> i64 {
+                self.0.timestamp_millis()
+            }
+
             /// Parse a timestamp from an RFC 3339 date and time string.
             ///
             /// # Examples
@@ -182,6 +197,24 @@ cfg_if::cfg_if! {
                 self.0.unix_timestamp()
             }
 
+            /// Create a new `Timestamp` from a UNIX timestamp in milliseconds.
+            ///
+            /// # Errors
+            ///
+            /// Returns `Err` if the value is invalid. The valid range of the value may vary depending on
+            /// the feature flags enabled (`time` with `large-dates`).
+            pub fn from_unix_millis(millis: i64) -> Result<Self, InvalidTimestamp> {
+                let ns = i128::from(millis) * 1_000_000;
+                let dt = OffsetDateTime::from_unix_timestamp_nanos(ns).map_err(|_| InvalidTimestamp)?;
+                Ok(Self(dt))
+            }
+
+            /// Returns the number of non-leap milliseconds since January 1, 1970 0:00:00 UTC
+            #[must_use]
+            pub fn unix_millis(&self) -> i64 {
+                (self.0.unix_timestamp_nanos() / 1_000_000) as i64
+            }
+
             /// Parse a timestamp from an RFC 3339 date and time string.
             ///
             /// # Examples
@@ -284,6 +317,73 @@ impl From<&Timestamp> for Timestamp {
     }
 }
 
+/// Used with `#[serde(with = "millis")]`, (de)serializing a [`Timestamp`]
+/// as an integer UNIX timestamp in milliseconds, as used by activities.
+pub mod millis {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Timestamp;
+
+    /// Deserializes a timestamp from integer milliseconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value is not an integer, or is out of range.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+        let millis = i64::deserialize(deserializer)?;
+
+        Timestamp::from_unix_millis(millis).map_err(Error::custom)
+    }
+
+    /// Serializes a timestamp as integer milliseconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the serializer fails.
+    pub fn serialize<S: Serializer>(value: &Timestamp, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(value.unix_millis())
+    }
+
+    /// Used with `#[serde(with = "millis::option")]`
+    pub mod option {
+        use serde::de::Error;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        use super::Timestamp;
+
+        /// Deserializes an optional timestamp from integer milliseconds.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the value is neither null nor an integer, or is
+        /// out of range.
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Timestamp>, D::Error> {
+            let millis = Option::<i64>::deserialize(deserializer)?;
+
+            millis.map(Timestamp::from_unix_millis).transpose().map_err(Error::custom)
+        }
+
+        /// Serializes an optional timestamp as integer milliseconds.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the serializer fails.
+        #[allow(clippy::ref_option)]
+        pub fn serialize<S: Serializer>(
+            value: &Option<Timestamp>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => serializer.serialize_some(&value.unix_millis()),
+                None => serializer.serialize_none(),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Timestamp;
@@ -298,4 +398,12 @@ mod tests {
             assert_eq!(timestamp.to_string(), "2016-04-30T11:18:25Z");
         }
     }
+
+    #[test]
+    fn from_unix_millis() {
+        let timestamp = Timestamp::from_unix_millis(1462015105796).unwrap();
+        assert_eq!(timestamp.unix_timestamp(), 1462015105);
+        assert_eq!(timestamp.unix_millis(), 1462015105796);
+        assert_eq!(timestamp.to_string(), "2016-04-30T11:18:25.796Z");
+    }
 }
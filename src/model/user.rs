@@ -192,6 +192,51 @@ pub struct CurrentUser {
     pub accent_colour: Option<Colour>,
     #[cfg(not(feature = "utils"))]
     pub accent_colour: Option<u32>,
+    /// The type of Nitro subscription of the user.
+    #[serde(default)]
+    pub premium_type: PremiumType,
+}
+
+/// The type of Nitro subscription of a user.
+///
+/// [Discord docs](https://discord.com/developers/docs/resources/user#user-object-premium-types).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum PremiumType {
+    /// The user has no Nitro subscription.
+    None = 0,
+    /// The user has a Nitro Classic subscription.
+    NitroClassic = 1,
+    /// The user has a Nitro subscription.
+    Nitro = 2,
+    /// The user has a Nitro Basic subscription.
+    NitroBasic = 3,
+    /// The subscription is of an unknown type.
+    Unknown = !0,
+}
+
+enum_number!(PremiumType {
+    None,
+    NitroClassic,
+    Nitro,
+    NitroBasic,
+});
+
+impl PremiumType {
+    /// Whether this is any kind of Nitro subscription.
+    ///
+    /// Unknown subscription types are assumed to be Nitro subscriptions, as
+    /// they are likely new tiers.
+    #[must_use]
+    pub fn is_nitro(self) -> bool {
+        self != PremiumType::None
+    }
+}
+
+impl Default for PremiumType {
+    fn default() -> Self {
+        PremiumType::None
+    }
 }
 
 #[cfg(feature = "model")]
@@ -606,6 +651,70 @@ impl OnlineStatus {
             OnlineStatus::Online => "online",
         }
     }
+
+    /// Returns the status as it is broadcast to other users.
+    ///
+    /// [`Invisible`] is only seen as such by the current user, and appears as
+    /// [`Offline`] to everyone else. Other statuses are unchanged.
+    ///
+    /// [`Invisible`]: OnlineStatus::Invisible
+    /// [`Offline`]: OnlineStatus::Offline
+    #[must_use]
+    pub fn as_seen_by_others(&self) -> OnlineStatus {
+        match *self {
+            OnlineStatus::Invisible => OnlineStatus::Offline,
+            other => other,
+        }
+    }
+
+    /// Whether other users see this status as anything other than offline.
+    #[must_use]
+    pub fn is_visible_to_others(&self) -> bool {
+        self.as_seen_by_others() != OnlineStatus::Offline
+    }
+
+    /// Returns a coloured circle emoji representing the status: 🟢 when online,
+    /// 🟡 when idle, 🔴 when on do not disturb, and ⚫ when offline.
+    ///
+    /// [`Invisible`] is represented like [`Offline`].
+    ///
+    /// [`Invisible`]: OnlineStatus::Invisible
+    /// [`Offline`]: OnlineStatus::Offline
+    #[must_use]
+    pub fn status_emoji(&self) -> &'static str {
+        match *self {
+            OnlineStatus::Online => "\u{1F7E2}",
+            OnlineStatus::Idle => "\u{1F7E1}",
+            OnlineStatus::DoNotDisturb => "\u{1F534}",
+            OnlineStatus::Invisible | OnlineStatus::Offline => "\u{26AB}",
+        }
+    }
+
+    /// Returns the colour the official client uses for the status, as a hex
+    /// code: green when online, yellow when idle, red when on do not disturb,
+    /// and grey when offline.
+    ///
+    /// [`Invisible`] is represented like [`Offline`].
+    ///
+    /// [`Invisible`]: OnlineStatus::Invisible
+    /// [`Offline`]: OnlineStatus::Offline
+    #[must_use]
+    pub const fn color(&self) -> u32 {
+        match *self {
+            OnlineStatus::Online => 0x43B581,
+            OnlineStatus::Idle => 0xFAA61A,
+            OnlineStatus::DoNotDisturb => 0xF04747,
+            OnlineStatus::Invisible | OnlineStatus::Offline => 0x747F8D,
+        }
+    }
+
+    /// Returns the red, green, and blue components of [`Self::color`].
+    #[must_use]
+    pub const fn color_rgb(&self) -> (u8, u8, u8) {
+        let color = self.color();
+
+        ((color >> 16) as u8, (color >> 8) as u8, color as u8)
+    }
 }
 
 impl Default for OnlineStatus {
@@ -614,6 +723,52 @@ impl Default for OnlineStatus {
     }
 }
 
+/// A relationship between the current user and another user, such as a
+/// friendship or a block.
+///
+/// These are only sent to user accounts.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Relationship {
+    /// The Id of the other user.
+    pub id: UserId,
+    /// The kind of relationship.
+    #[serde(rename = "type")]
+    pub kind: RelationshipType,
+    /// The nickname the current user gave the other user, if any.
+    #[serde(default)]
+    pub nickname: Option<String>,
+    /// The other user, if it was included.
+    #[serde(default)]
+    pub user: Option<User>,
+}
+
+/// The kind of a [`Relationship`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum RelationshipType {
+    /// The users are friends.
+    Friend = 1,
+    /// The current user blocked the other user.
+    Blocked = 2,
+    /// The other user sent a friend request to the current user.
+    IncomingRequest = 3,
+    /// The current user sent a friend request to the other user.
+    OutgoingRequest = 4,
+    /// The users have interacted, without being friends.
+    Implicit = 5,
+    /// A relationship of unknown kind.
+    Unknown = !0,
+}
+
+enum_number!(RelationshipType {
+    Friend,
+    Blocked,
+    IncomingRequest,
+    OutgoingRequest,
+    Implicit
+});
+
 /// Information about a user.
 ///
 /// [Discord docs](https://discord.com/developers/docs/resources/user#user-object).
@@ -1379,6 +1534,62 @@ mod test {
         ]);
     }
 
+    #[test]
+    fn test_online_status_as_seen_by_others() {
+        use super::OnlineStatus;
+
+        let cases = [
+            (OnlineStatus::DoNotDisturb, OnlineStatus::DoNotDisturb, true),
+            (OnlineStatus::Idle, OnlineStatus::Idle, true),
+            (OnlineStatus::Invisible, OnlineStatus::Offline, false),
+            (OnlineStatus::Offline, OnlineStatus::Offline, false),
+            (OnlineStatus::Online, OnlineStatus::Online, true),
+        ];
+
+        for (status, seen, visible) in cases {
+            assert_eq!(status.as_seen_by_others(), seen);
+            assert_eq!(status.is_visible_to_others(), visible);
+        }
+    }
+
+    #[test]
+    fn test_online_status_color() {
+        use super::OnlineStatus;
+
+        assert_eq!(OnlineStatus::Online.color(), 0x43B581);
+        assert_eq!(OnlineStatus::DoNotDisturb.color_rgb(), (0xF0, 0x47, 0x47));
+        assert_eq!(OnlineStatus::Invisible.color(), OnlineStatus::Offline.color());
+    }
+
+    #[test]
+    fn test_current_user_premium_type() {
+        use super::{CurrentUser, PremiumType};
+        use crate::json::{self, json};
+
+        let user = |premium_type| -> CurrentUser {
+            let mut value = json!({
+                "id": "1",
+                "username": "user",
+                "discriminator": "0001",
+                "mfa_enabled": false,
+            });
+            if let Some(premium_type) = premium_type {
+                value["premium_type"] = json!(premium_type);
+            }
+
+            json::from_value(value).unwrap()
+        };
+
+        assert_eq!(user(None).premium_type, PremiumType::None);
+        assert_eq!(user(Some(2)).premium_type, PremiumType::Nitro);
+        assert_eq!(user(Some(3)).premium_type, PremiumType::NitroBasic);
+        assert_eq!(user(Some(9)).premium_type, PremiumType::Unknown);
+
+        assert!(!PremiumType::None.is_nitro());
+        assert!(PremiumType::NitroClassic.is_nitro());
+        assert!(PremiumType::Unknown.is_nitro());
+    }
+
     #[cfg(feature = "model")]
     mod model {
         use crate::model::user::User;
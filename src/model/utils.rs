@@ -180,16 +180,28 @@ pub mod presences {
     pub use super::serialize_map_values as serialize;
 }
 
+/// Deserializes activity buttons, either as the bare labels sent by the
+/// gateway or as full button objects, such as those previously serialized.
 pub fn deserialize_buttons<'de, D: Deserializer<'de>>(
     deserializer: D,
 ) -> StdResult<Vec<ActivityButton>, D::Error> {
-    let labels = Vec::deserialize(deserializer)?;
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawButton {
+        Label(String),
+        Button(ActivityButton),
+    }
+
+    let raw = Vec::deserialize(deserializer)?;
     let mut buttons = vec![];
 
-    for label in labels {
-        buttons.push(ActivityButton {
-            label,
-            url: String::new(),
+    for button in raw {
+        buttons.push(match button {
+            RawButton::Label(label) => ActivityButton {
+                label,
+                url: String::new(),
+            },
+            RawButton::Button(button) => button,
         });
     }
 
@@ -343,6 +355,25 @@ pub fn serialize_map_values<K, S: Serializer, V: Serialize>(
     seq.end()
 }
 
+/// Serializes activities to be sent to the gateway, leaving out the fields not
+/// modelled by the library.
+pub fn serialize_outbound_activities<S: Serializer>(
+    activities: &[Activity],
+    serializer: S,
+) -> StdResult<S::Ok, S::Error> {
+    #[cfg(feature = "presence_extra_fields")]
+    {
+        serializer.collect_seq(activities.iter().map(|activity| Activity {
+            extra: HashMap::new(),
+            ..activity.clone()
+        }))
+    }
+    #[cfg(not(feature = "presence_extra_fields"))]
+    {
+        serializer.collect_seq(activities)
+    }
+}
+
 /// Tries to find a user's permissions using the cache.
 /// Unlike [`user_has_perms`], this function will return `true` even when
 /// the permissions are not in the cache.
@@ -0,0 +1,117 @@
+//! Helpers to test the (de)serialization of models against payloads captured
+//! from Discord.
+//!
+//! This is useful to check that payloads seen in the wild can be handled,
+//! both in this library and in downstream crates. Requires the `test_util`
+//! feature.
+
+use crate::json::prelude::{from_str, from_value, to_value};
+use crate::model::gateway::Presence;
+
+/// Representative presence payloads, as sent in `PRESENCE_UPDATE` events.
+pub mod fixtures {
+    /// A user listening to Spotify.
+    pub const SPOTIFY_PRESENCE: &str = r#"{
+        "user": {"id": "80351110224678912"},
+        "status": "online",
+        "guild_id": "81384788765712384",
+        "client_status": {"desktop": "online"},
+        "activities": [{
+            "type": 2,
+            "name": "Spotify",
+            "id": "spotify:1",
+            "flags": 48,
+            "details": "Song title",
+            "state": "Artist; Other artist",
+            "sync_id": "6rqhFgbbKwnb9MLmUQDhG6",
+            "session_id": "e8d5b3a41f4f5e3ad6bca2a3bc6ca2b7",
+            "party": {"id": "spotify:80351110224678912"},
+            "assets": {
+                "large_image": "spotify:ab67616d0000b273a8f4a0fbd5c4b0a6d1c1e5e2",
+                "large_text": "Album title"
+            },
+            "timestamps": {"start": 1650000000000, "end": 1650000200000},
+            "created_at": 1650000000123
+        }]
+    }"#;
+
+    /// A user with a custom status with a custom emoji.
+    pub const CUSTOM_STATUS_PRESENCE: &str = r#"{
+        "user": {"id": "80351110224678912"},
+        "status": "dnd",
+        "client_status": {"desktop": "dnd", "mobile": "dnd"},
+        "activities": [{
+            "type": 4,
+            "name": "Custom Status",
+            "id": "custom",
+            "state": "Do not disturb",
+            "emoji": {"name": "sleepy", "id": "396521773144866826", "animated": true},
+            "created_at": 1650000000123
+        }]
+    }"#;
+
+    /// A user streaming on Twitch.
+    pub const STREAMING_PRESENCE: &str = r#"{
+        "user": {"id": "80351110224678912"},
+        "status": "online",
+        "guild_id": "81384788765712384",
+        "client_status": {"web": "online"},
+        "activities": [{
+            "type": 1,
+            "name": "Twitch",
+            "id": "2a1f6c2fd2d4e3a",
+            "url": "https://www.twitch.tv/someone",
+            "details": "Speedrunning",
+            "state": "Game",
+            "assets": {"large_image": "twitch:someone"},
+            "created_at": 1650000000123
+        }]
+    }"#;
+
+    /// All the fixtures.
+    pub const ALL_PRESENCES: &[&str] =
+        &[SPOTIFY_PRESENCE, CUSTOM_STATUS_PRESENCE, STREAMING_PRESENCE];
+}
+
+/// Asserts that a raw presence payload can be deserialized into a
+/// [`Presence`], and that serializing it and deserializing the result yields
+/// the same presence.
+///
+/// # Panics
+///
+/// Panics if the payload can not be deserialized, or if the presence changed
+/// after the round trip.
+#[track_caller]
+pub fn assert_presence_roundtrips(json: &str) {
+    let mut json = json.to_string();
+    let presence: Presence = from_str(json.as_mut_str())
+        .unwrap_or_else(|why| panic!("Failed to deserialize presence: {}", why));
+
+    let value = to_value(&presence).expect("Failed to serialize presence");
+    let roundtripped: Presence = from_value(value.clone())
+        .unwrap_or_else(|why| panic!("Failed to deserialize serialized presence: {}", why));
+
+    assert_eq!(
+        to_value(&roundtripped).expect("Failed to serialize presence"),
+        value,
+        "Presence changed after a round trip"
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::{assert_presence_roundtrips, fixtures};
+
+    #[test]
+    fn test_presence_fixtures_roundtrip() {
+        for json in fixtures::ALL_PRESENCES {
+            assert_presence_roundtrips(json);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to deserialize presence")]
+    fn test_presence_roundtrip_invalid() {
+        assert_presence_roundtrips(r#"{"status": "online"}"#);
+    }
+}
@@ -25,12 +25,14 @@ pub use self::message_builder::{Content, ContentModifier, EmbedMessageBuilding,
 pub use self::token::{parse as parse_token, validate as validate_token};
 pub type Color = Colour;
 
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
 use crate::internal::prelude::*;
+use crate::model::gateway::{ActivityType, Presence};
 use crate::model::id::EmojiId;
 use crate::model::misc::EmojiIdentifier;
 
@@ -458,6 +460,37 @@ pub fn shard_id(guild_id: impl Into<u64>, shard_count: u64) -> u64 {
     (guild_id.into() >> 22) % shard_count
 }
 
+/// Groups presences by the URL of their [`Streaming`] activities, to find the
+/// users watching or hosting the same stream.
+///
+/// Presences without a streaming activity with a URL are excluded. A presence
+/// with several streaming activities is included in the group of each URL,
+/// but only once per group.
+///
+/// [`Streaming`]: ActivityType::Streaming
+#[must_use]
+pub fn group_by_stream(presences: &[Presence]) -> HashMap<Url, Vec<&Presence>> {
+    let mut groups: HashMap<Url, Vec<&Presence>> = HashMap::new();
+
+    for presence in presences {
+        let urls = presence
+            .activities
+            .iter()
+            .filter(|activity| activity.kind == ActivityType::Streaming)
+            .filter_map(|activity| activity.url.as_ref());
+
+        for url in urls {
+            let group = groups.entry(url.clone()).or_default();
+
+            if !matches!(group.last(), Some(last) if std::ptr::eq(*last, presence)) {
+                group.push(presence);
+            }
+        }
+    }
+
+    groups
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -509,4 +542,44 @@ mod test {
         assert_eq!(id, 245037420704169985);
         assert_eq!(token, "ig5AO-wdVWpCBtUUMxmgsWryqgsW3DChbKYOINftJ4DCrUbnkedoYZD0VOH1QLr-S3sV");
     }
+
+    #[test]
+    fn test_group_by_stream() {
+        use crate::json::{self, json};
+
+        let stream = |user_id: u64, urls: &[&str]| -> Presence {
+            let activities: Vec<_> =
+                urls.iter().map(|url| json!({"type": 1, "name": "Twitch", "url": url})).collect();
+
+            json::from_value(json!({
+                "user": {"id": user_id.to_string()},
+                "status": "online",
+                "activities": activities,
+            }))
+            .unwrap()
+        };
+
+        let first = "https://www.twitch.tv/first";
+        let second = "https://www.twitch.tv/second";
+        let presences = [
+            stream(1, &[first, first]),
+            stream(2, &[first, second]),
+            stream(3, &[]),
+            json::from_value(json!({
+                "user": {"id": "4"},
+                "status": "online",
+                "activities": [{"type": 0, "name": "Game", "url": first}],
+            }))
+            .unwrap(),
+        ];
+
+        let groups = group_by_stream(&presences);
+        let user_ids = |url: &str| -> Vec<u64> {
+            groups[&url.parse::<Url>().unwrap()].iter().map(|p| p.user.id.0).collect()
+        };
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(user_ids(first), vec![1, 2]);
+        assert_eq!(user_ids(second), vec![2]);
+    }
 }